@@ -3,13 +3,13 @@
 #[cfg(target_os = "freebsd")]
 extern crate bindgen;
 
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "embedded-drivedb"))]
 use std::env;
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "embedded-drivedb"))]
 use std::path::PathBuf;
 
 #[cfg(target_os = "freebsd")]
-fn main() {
+fn bindings() {
 	println!("cargo:rustc-link-lib=cam");
 
 	let bindings = bindgen::Builder::default()
@@ -41,6 +41,71 @@ fn main() {
 		.expect("Couldn't write bindings!");
 }
 
-#[cfg(not(target_os = "freebsd"))]
+// drivedb modules log a thing or two, but there's no logger in build scripts
+#[cfg(feature = "embedded-drivedb")]
+macro_rules! warn { ($($arg:tt)*) => { let _ = format_args!($($arg)*); } }
+#[cfg(feature = "embedded-drivedb")]
+macro_rules! debug { ($($arg:tt)*) => { let _ = format_args!($($arg)*); } }
+
+// the very same code that loads drivedb files at runtime
+#[cfg(feature = "embedded-drivedb")]
+#[path = "src/drivedb/preprocessor.rs"]
+#[allow(dead_code)]
+mod preprocessor;
+#[cfg(feature = "embedded-drivedb")]
+#[path = "src/drivedb/parser.rs"]
+#[allow(dead_code)]
+mod parser;
+
+/*
+Parses drivedb snapshot and writes its entries as Rust source for `src/drivedb/builtin.rs` to include.
+
+Snapshot is read from `drivedb/drivedb.h`, unless `HDD_DRIVEDB` environment variable points to some other file. If there's no snapshot, the build goes on with an empty table: the snapshot is not shipped with the crate, and e.g. `--all-features` builds should not fail because of that.
+*/
+#[cfg(feature = "embedded-drivedb")]
+fn embed_drivedb() {
+	use std::fmt::Write;
+
+	println!("cargo:rerun-if-env-changed=HDD_DRIVEDB");
+	println!("cargo:rerun-if-changed=src/drivedb/preprocessor.rs");
+	println!("cargo:rerun-if-changed=src/drivedb/parser.rs");
+
+	let path = env::var("HDD_DRIVEDB").unwrap_or_else(|_| "drivedb/drivedb.h".to_string());
+	println!("cargo:rerun-if-changed={}", path);
+
+	let entries = match std::fs::read_to_string(&path) {
+		Ok(db) => {
+			let db = preprocessor::preprocess(&db)
+				.unwrap_or_else(|e| panic!("Cannot parse drivedb snapshot {}: line {}: {}", path, e.line, e.message));
+			match parser::database(&db) {
+				Ok((_, entries)) => entries,
+				Err(e) => panic!("Cannot parse drivedb snapshot {}: {:?}", path, e),
+			}
+		},
+		Err(e) => {
+			println!("cargo:warning=Cannot read drivedb snapshot {}: {}; built-in drivedb will be empty, see drivedb/README.md", path, e);
+			vec![]
+		},
+	};
+
+	// Debug representation of strings happens to be a valid Rust string literal
+	let mut out = String::new();
+	writeln!(out, "// generated by build.rs from {:?}", path).unwrap();
+	writeln!(out, "pub(crate) static ENTRIES: &[(&str, &str, &str, &str, &str, usize)] = &[").unwrap();
+	for e in entries {
+		writeln!(out, "\t({:?}, {:?}, {:?}, {:?}, {:?}, {}),", e.family, e.model, e.firmware, e.warning, e.presets, e.line).unwrap();
+	}
+	writeln!(out, "];").unwrap();
+
+	let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+	std::fs::write(out_path.join("drivedb.rs"), out)
+		.expect("Couldn't write drivedb snapshot!");
+}
+
 fn main() {
+	#[cfg(target_os = "freebsd")]
+	bindings();
+
+	#[cfg(feature = "embedded-drivedb")]
+	embed_drivedb();
 }
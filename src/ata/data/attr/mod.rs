@@ -9,6 +9,7 @@ pub struct SmartAttribute {
 	pub id: u8,
 
 	pub name: Option<String>, // comes from the drivedb
+	pub increasing: bool, // raw value is a counter that is never reset; comes from the drivedb
 
 	pub pre_fail: bool, // if true, failure is predicted within 24h; otherwise, attribute indicates drive's exceeded intended design life period
 	pub online: bool,
@@ -23,12 +24,87 @@ pub struct SmartAttribute {
 	// contains None if `raw` is rendered using byte that usually covers this value
 	// TODO? 0x00 | 0xfe | 0xff are invalid
 	pub value: Option<u8>,
-	// contains None if `raw` is rendered using byte that usually covers this value
+	// contains None if `raw` is rendered using byte that usually covers this value, or the one of normalized value
 	pub worst: Option<u8>,
 
 	pub raw: raw::Raw,
 
-	pub thresh: Option<u8>, // requested separately; TODO? 0x00 is "always passing", 0xff is "always failing", 0xfe is invalid
+	pub thresh: Option<u8>, // requested separately; 0x00 is "always passing", 0xff is "always failing", 0xfe is invalid; see `state()`
+}
+
+/// Attribute state, as evaluated from its normalized values and threshold (see `SmartAttribute::state()`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum AttributeState {
+	Ok,
+	/// normalized value is at or below the threshold (smartctl: `FAILING_NOW`)
+	FailingNow,
+	/// normalized value is above the threshold, but the worst one is not (smartctl: `In_the_past`)
+	FailedInPast,
+	/// threshold is not available, or is zero, which means the attribute always passes
+	NoThreshold,
+	/// threshold is 0xfe, which is invalid
+	InvalidThreshold,
+	/// normalized value is not available, as the bytes that usually hold it are part of the raw value (see drivedb)
+	NoValue,
+}
+
+/// Drive-level verdict, based on the states of all the attributes (see [`verdict()`](fn.verdict.html)), from the best to the worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Verdict {
+	/// no attribute is or was failing
+	Good,
+	/// some attributes were failing in the past, but none are failing now
+	FailedInPast,
+	/// some old-age (non-pre-failure) attributes are failing now: drive has probably exceeded its intended design life
+	OldAgeFailing,
+	/// some pre-failure attributes are failing now: drive failure is predicted within 24 hours
+	PreFailFailing,
+}
+
+impl SmartAttribute {
+	/**
+	Evaluates attribute state, following the rules smartctl uses to fill the WHEN_FAILED column:
+
+	* zero threshold means the attribute always passes (ATA-3), so there's nothing to evaluate,
+	* attribute is failing now if its normalized value is less than or equal to the threshold,
+	* attribute has failed in the past if its worst value is less than or equal to the threshold.
+	*/
+	pub fn state(&self) -> AttributeState {
+		use self::AttributeState::*;
+
+		let value = match self.value {
+			Some(value) => value,
+			None => return NoValue,
+		};
+		let thresh = match self.thresh {
+			None | Some(0x00) => return NoThreshold,
+			Some(0xfe) => return InvalidThreshold,
+			Some(thresh) => thresh,
+		};
+
+		if value <= thresh {
+			FailingNow
+		} else if self.worst.map(|worst| worst <= thresh).unwrap_or(false) {
+			FailedInPast
+		} else {
+			Ok
+		}
+	}
+}
+
+/// Evaluates the states of all the attributes and returns the worst verdict they imply.
+pub fn verdict(attrs: &[SmartAttribute]) -> Verdict {
+	attrs.iter()
+		.map(|attr| match (attr.state(), attr.pre_fail) {
+			(AttributeState::FailingNow, true) => Verdict::PreFailFailing,
+			(AttributeState::FailingNow, false) => Verdict::OldAgeFailing,
+			(AttributeState::FailedInPast, _) => Verdict::FailedInPast,
+			_ => Verdict::Good,
+		})
+		.max()
+		.unwrap_or(Verdict::Good)
 }
 
 pub fn parse_smart_values(data: &Vec<u8>, raw_thresh: &Vec<u8>, meta: &Option<drivedb::DriveMeta>) -> Option<Vec<SmartAttribute>> {
@@ -59,7 +135,8 @@ pub fn parse_smart_values(data: &Vec<u8>, raw_thresh: &Vec<u8>, meta: &Option<dr
 		let id = data[offset];
 
 		let attr = meta.as_ref().map(|meta| meta.render_attribute(id)).unwrap_or(None);
-		let is_in_raw = |c| attr.as_ref().map(|a| a.byte_order.contains(c)).unwrap_or(false);
+		let no_normval = attr.as_ref().map(|a| a.no_normval()).unwrap_or(false);
+		let no_worstval = attr.as_ref().map(|a| a.no_worstval()).unwrap_or(false);
 
 		attrs.push(SmartAttribute {
 			id: id,
@@ -68,6 +145,7 @@ pub fn parse_smart_values(data: &Vec<u8>, raw_thresh: &Vec<u8>, meta: &Option<dr
 				Some(a) => a.name.clone(),
 				None => None
 			},
+			increasing: attr.as_ref().map(|a| a.increasing).unwrap_or(false),
 
 			pre_fail:        flags & (1<<0) != 0,
 			online:          flags & (1<<1) != 0,
@@ -77,10 +155,10 @@ pub fn parse_smart_values(data: &Vec<u8>, raw_thresh: &Vec<u8>, meta: &Option<dr
 			self_preserving: flags & (1<<5) != 0,
 			flags:           flags & (!0b11_1111),
 
-			value: if !is_in_raw('v') {
+			value: if !no_normval {
 				Some(data[offset + 3])
 			} else { None },
-			worst: if !is_in_raw('w') {
+			worst: if !no_worstval {
 				Some(data[offset + 4])
 			} else { None },
 
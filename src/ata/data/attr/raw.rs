@@ -2,31 +2,26 @@ use std::cmp::{min, max};
 use crate::drivedb;
 use std::fmt;
 
-// Initially I used `BigEndian` from the `byteorder` crate; however, it quickly resulted in an iterator mess (`.chunks()`, `.take()`, `.skip()`, `.map()`, `.unwrap()` et al.), and it also did not help with 24-bit and 48-bit packed values at all.
-fn read(data: &[u8], bits: usize) -> u64 {
-	let mut out: u64 = 0;
-	for i in 0..(bits/8) {
-		out <<= 8;
-		out += data[i] as u64;
-	}
-	out
-}
-
 #[derive(Debug)]
 #[cfg_attr(feature = "serializable", derive(Serialize))]
 pub enum Raw {
 	Raw8(Vec<u8>),
 	Raw16(Vec<u16>),
 	Raw64(u64),
+	/// value and the number of bits it was rendered from (48, 56 or 64), for `hex48`, `hex56` and `hex64` formats
+	Hex(u64, u8),
 	Raw16opt16(u16, Option<Vec<u16>>),
 	Raw16avg16 { value: u16, average: u16 },
 	Raw24opt8(u32, Option<Vec<u8>>),
 	Raw24div(u32, u32),
-	Minutes(u64),
+	/// minutes and, optionally, some vendor-specific counter from the most significant word
+	Minutes(u64, Option<u16>),
 	Seconds(u64),
 	HoursMilliseconds(u32, u32),
 	Celsius(f32),
-	CelsiusMinMax { current: u8, min: u8, max: u8 },
+	CelsiusMinMax { current: i8, min: i8, max: i8 },
+	/// same as `CelsiusMinMax`, with the number of times temperature went over the limit (WDC)
+	CelsiusMinMaxCount { current: i8, min: i8, max: i8, count: u16 },
 }
 
 fn write_vec<T>(f: &mut fmt::Formatter, vec: &Vec<T>) -> fmt::Result
@@ -44,6 +39,7 @@ impl fmt::Display for Raw {
 			Raw8(vals) => write_vec(f, &vals),
 			Raw16(vals) => write_vec(f, &vals),
 			Raw64(val) => write!(f, "{}", val),
+			Hex(val, bits) => write!(f, "0x{:0width$x}", val, width = *bits as usize / 4),
 			Raw16opt16(x, y) => {
 				write!(f, "{}", x)?;
 				if let Some(vec) = y {
@@ -62,14 +58,19 @@ impl fmt::Display for Raw {
 				}
 				Ok(())
 			}
+			Raw16avg16 { value, average: 0 } => write!(f, "{}", value),
 			Raw16avg16 { value, average } =>
 				write!(f, "{} (avg: {})", value, average),
 			Raw24div(x, y) => write!(f, "{}/{}", x, y),
-			Minutes(m) => {
+			Minutes(m, x) => {
 				let h = m / 60; let m = m - h * 60;
 				let d = h / 24; let h = h - d * 24;
 
-				write!(f, "{}d {:02}:{:02}", d, h, m)
+				write!(f, "{}d {:02}:{:02}", d, h, m)?;
+				if let Some(x) = x {
+					write!(f, " ({})", x)?;
+				}
+				Ok(())
 			},
 			Seconds(s) => {
 				let m = s / 60; let s = s - m * 60;
@@ -88,14 +89,18 @@ impl fmt::Display for Raw {
 			Celsius(cur) => write!(f, "{:.1}°C", cur), // .1 because f32
 			CelsiusMinMax { current, min, max } =>
 				write!(f, "{}°C (min: {}°C, max: {}°C)", current, min, max),
+			CelsiusMinMaxCount { current, min, max, count } =>
+				write!(f, "{}°C (min: {}°C, max: {}°C, over limit: {})", current, min, max, count),
 		}
 	}
 }
 
-// In smartmontools, they first apply byte order attribute to get the u64, which in turn is used to get separate u8/u16s for RAWFMT_RAW8/RAWFMT_RAW16; there's also different byte order defaults for different formats. Oh for crying out loud…
+/*
+Just like smartmontools does (see `ata_get_attr_raw_value()` and `ata_format_attr_raw_value()` in atacmds.cpp), we first apply the byte order to get the single integer value (48 bits for most of the formats, up to 64 bits for some), and only then split its lower 48 bits into separate bytes and words for the formats that need those. Default byte orders differ for different formats; these are set by the drivedb parser (see `drivedb::vendor_attribute`).
 
-// `data` is a slice that contains all the attribute data, including attribute id
-fn reorder(data: &[u8], byte_order: &str) -> Vec<u8> {
+`data` is a slice that contains all the attribute data, including attribute id.
+*/
+fn raw_value(data: &[u8], byte_order: &str) -> u64 {
 	byte_order.chars().map(|c| match c {
 		'v' => data[3], // value
 		'w' => data[4], // worst
@@ -106,114 +111,240 @@ fn reorder(data: &[u8], byte_order: &str) -> Vec<u8> {
 		'4' => data[9],
 		'5' => data[10],
 		'r' => data[11], // reserved byte
-		// smartmontools defaults to 0 for any unrecognized character;
-		// we'll use '_' later for padding
+		// smartmontools defaults to 0 for any unrecognized character
 		_ => 0,
-	}).collect()
+	}).fold(0u64, |value, b| (value << 8) | b as u64)
+}
+
+/*
+This is a chart of all possible raw value interpretations for attributes of type 'tempminmax', from smartmontools:
+> [5][4][3][2][1][0] raw[]
+> [ 2 ] [ 1 ] [ 0 ]  word[]
+> xx HH xx LL xx TT (Hitachi/HGST)
+> xx LL xx HH xx TT (Kingston SSDs)
+> 00 00 HH LL xx TT (Maxtor, Samsung, Seagate, Toshiba)
+> 00 00 00 HH LL TT (WDC)
+> CC CC HH LL xx TT (WDC, CCCC=over temperature count)
+> (xx = 00/ff, possibly sign extension of lower byte)
+
+Unless the layout is set explicitly with 'tempminmax#N' format, these generalized patterns are tried, in order, and only accepted if the values look like temperatures (see `check_temp_word()` and `check_temp_range()`):
+1. 00 00 00 00 xx TT
+2. 00 00 HL LH xx TT
+3. 00 00 00 HL LH TT
+4. xx HL xx LH xx TT
+5. CC CC HL LH xx TT
+*/
+
+// smartmontools: check_temp_word(); 0x11 for non-negative signed byte or word, 0x01 for negative signed byte, 0x10 for negative signed word, 0x00 if it's neither
+fn check_temp_word(word: u16) -> u8 {
+	match word {
+		0x0000 ..= 0x007f => 0x11,
+		0x0080 ..= 0x00ff => 0x01,
+		0xff80 ..= 0xffff => 0x10,
+		_ => 0x00,
+	}
+}
+
+// smartmontools: check_temp_range(); returns min and max if they look like temperatures, with the current one in between
+fn check_temp_range(t: i8, x: u8, y: u8) -> Option<(i8, i8)> {
+	let (lo, hi) = (min(x as i8, y as i8), max(x as i8, y as i8));
+	if -60 <= lo && lo <= t && t <= hi && hi <= 120 && !(lo == -1 && hi <= 0) {
+		Some((lo, hi))
+	} else { None }
+}
+
+fn tempminmax(raw: &[u8; 6], word: &[u16; 3], layout: Option<u8>) -> Option<Raw> {
+	use self::Raw::*;
+
+	let t = raw[0] as i8;
+	let minmax = |(min, max)| CelsiusMinMax { current: t, min, max };
+	let minmaxcount = |(min, max)| CelsiusMinMaxCount { current: t, min, max, count: word[2] };
+
+	if let Some(layout) = layout {
+		let sorted = |x: u8, y: u8| (min(x as i8, y as i8), max(x as i8, y as i8));
+		return match layout {
+			1 => Some(Celsius(t as f32)),
+			2 => Some(minmax(sorted(raw[2], raw[3]))),
+			3 => Some(minmax(sorted(raw[1], raw[2]))),
+			4 => Some(minmax(sorted(raw[2], raw[4]))),
+			5 => Some(minmaxcount(sorted(raw[2], raw[3]))),
+			_ => None,
+		};
+	}
+
+	let ctw0 = check_temp_word(word[0]);
+	if word[2] == 0 {
+		if word[1] == 0 && ctw0 != 0 {
+			return Some(Celsius(t as f32));
+		}
+		let range = if ctw0 != 0 { check_temp_range(t, raw[2], raw[3]) } else { None };
+		range
+			.or_else(|| if raw[3] == 0 { check_temp_range(t, raw[1], raw[2]) } else { None })
+			.map(minmax)
+	} else if ctw0 != 0 {
+		if ctw0 & check_temp_word(word[1]) & check_temp_word(word[2]) != 0
+			&& let Some(range) = check_temp_range(t, raw[2], raw[4])
+		{
+			Some(minmax(range))
+		} else if word[2] < 0x7fff
+			&& let Some(range) = check_temp_range(t, raw[2], raw[3])
+			&& range.1 >= 40
+		{
+			Some(minmaxcount(range))
+		} else { None }
+	} else { None }
 }
 
 impl Raw {
-	// `data`: see above
+	/**
+	Renders raw value of the attribute, using format and byte order from its description, if there's one. Defaults to `raw48` and `543210`.
+
+	`data` is an attribute entry from SMART READ DATA response, starting with the attribute id.
+
+	Supported formats are the same as in smartmontools (see `-v` in smartctl(8)): `raw8`, `raw16`, `raw48`, `raw56`, `raw64`, `hex48`, `hex56`, `hex64`, `raw16(raw16)`, `raw16(avg16)`, `raw24(raw8)`, `raw24/raw24`, `raw24/raw32`, `sec2hour`, `min2hour`, `halfmin2hour`, `msec24hour32`, `tempminmax`, `temp10x`. For `tempminmax`, specific min/max layout can be chosen by appending `#N`, where `N` is the number of the pattern from the list in the source code of this module; otherwise the layout is detected automatically.
+
+	## Example
+
+	These are the values smartmontools would extract from the same data, rendered the way this crate renders them:
+
+	```
+	use hdd::ata::data::attr::raw::Raw;
+	use hdd::drivedb::vendor_attribute;
+
+	// raw bytes are listed the way they are stored, least significant first
+	fn entry(value: u8, worst: u8, raw: [u8; 6], reserved: u8) -> Vec<u8> {
+		let mut data = vec![1, 0x00, 0x00, value, worst];
+		data.extend(&raw);
+		data.push(reserved);
+		data
+	}
+
+	let table = [
+		("1,raw48", entry(100, 100, [0x10, 0, 0, 0, 0, 0], 0), "16"),
+		("1,raw8", entry(100, 100, [1, 2, 3, 4, 5, 6], 0), "6 5 4 3 2 1"),
+		("1,raw16", entry(100, 100, [1, 0, 2, 0, 3, 0], 0), "3 2 1"),
+		("1,hex48", entry(100, 100, [0xef, 0xbe, 0xad, 0xde, 0, 0], 0), "0x0000deadbeef"),
+		("1,raw56", entry(100, 100, [1, 0, 0, 0, 0, 0], 1), "281474976710657"),
+		("1,hex56", entry(100, 100, [1, 0, 0, 0, 0, 0], 1), "0x01000000000001"),
+		("1,raw64", entry(100, 99, [1, 0, 0, 0, 0, 0], 0), "90980"),
+		("1,hex64", entry(100, 99, [1, 0, 0, 0, 0, 0], 0), "0x0000000000016364"),
+		("1,raw48:10", entry(100, 100, [1, 2, 0, 0, 0, 0], 0), "513"),
+		("197,raw48+", entry(100, 100, [0x10, 0, 0, 0, 0, 0], 0), "16"),
+		("1,hex48+:z543210", entry(100, 100, [0xef, 0xbe, 0xad, 0xde, 0, 0], 0), "0x0000deadbeef"),
+		("5,raw16(raw16)", entry(100, 100, [5, 0, 0, 0, 0, 0], 0), "5"),
+		("5,raw16(raw16)", entry(100, 100, [5, 0, 2, 0, 1, 0], 0), "5 (1 2)"),
+		("1,raw16(avg16)", entry(100, 100, [10, 0, 0, 0, 0, 0], 0), "10"),
+		("1,raw16(avg16)", entry(100, 100, [10, 0, 7, 0, 0, 0], 0), "10 (avg: 7)"),
+		("1,raw24(raw8)", entry(100, 100, [0x10, 0, 0, 0, 0, 0], 0), "16"),
+		("1,raw24(raw8)", entry(100, 100, [0x10, 0, 0, 1, 2, 3], 0), "16 (3 2 1)"),
+		("193,raw24/raw24", entry(100, 100, [5, 0, 0, 7, 0, 0], 0), "7/5"),
+		("1,raw24/raw32", entry(100, 100, [5, 0, 0, 0, 7, 0], 0), "7/5"),
+		("9,msec24hour32", entry(100, 100, [0x10, 0, 0, 0, 0xe8, 0x03], 0), "0d 16:00:01"),
+		("9,sec2hour", entry(100, 100, [0x3d, 0x0e, 0, 0, 0, 0], 0), "0d 01:00:45"),
+		("9,min2hour", entry(100, 100, [61, 0, 0, 0, 0, 0], 0), "0d 01:01"),
+		("9,min2hour", entry(100, 100, [61, 0, 0, 0, 5, 0], 0), "0d 01:01 (5)"),
+		("9,halfmin2hour", entry(100, 100, [120, 0, 0, 0, 0, 0], 0), "0d 01:00:00"),
+		("194,temp10x", entry(100, 100, [0x6b, 0x01, 0, 0, 0, 0], 0), "36.3°C"),
+		("194,tempminmax", entry(100, 100, [36, 0, 0, 0, 0, 0], 0), "36.0°C"),
+		("194,tempminmax", entry(100, 100, [0xfb, 0xff, 0, 0, 0, 0], 0), "-5.0°C"),
+		// xx HH xx LL xx TT (Hitachi/HGST)
+		("194,tempminmax", entry(100, 100, [36, 0, 20, 0, 45, 0], 0), "36°C (min: 20°C, max: 45°C)"),
+		// xx LL xx HH xx TT (Kingston SSDs)
+		("194,tempminmax", entry(100, 100, [36, 0, 45, 0, 20, 0], 0), "36°C (min: 20°C, max: 45°C)"),
+		// 00 00 HH LL xx TT (Maxtor, Samsung, Seagate, Toshiba)
+		("194,tempminmax", entry(100, 100, [36, 0, 20, 45, 0, 0], 0), "36°C (min: 20°C, max: 45°C)"),
+		("194,tempminmax", entry(100, 100, [0xfb, 0xff, 0xf6, 0x28, 0, 0], 0), "-5°C (min: -10°C, max: 40°C)"),
+		// 00 00 00 HH LL TT (WDC)
+		("194,tempminmax", entry(100, 100, [36, 20, 45, 0, 0, 0], 0), "36°C (min: 20°C, max: 45°C)"),
+		// CC CC HH LL xx TT (WDC, CCCC=over temperature count)
+		("194,tempminmax", entry(100, 100, [36, 0, 20, 45, 3, 0], 0), "36°C (min: 20°C, max: 45°C, over limit: 3)"),
+		("194,tempminmax", entry(100, 100, [36, 0, 0x34, 0x12, 0x78, 0x56], 0), "36°C (min: 18°C, max: 52°C, over limit: 22136)"),
+		// values that do not look like temperatures are shown as is
+		("194,tempminmax", entry(100, 100, [50, 0, 0x10, 0x27, 0, 0], 0), "655360050"),
+		("194,tempminmax", entry(100, 100, [36, 0xff, 20, 45, 0, 0], 0), "756350756"),
+		("194,tempminmax", entry(100, 100, [30, 0, 20, 35, 3, 0], 0), "13473415198"),
+		("194,tempminmax", entry(100, 100, [36, 0, 0x34, 0x12, 0xff, 0x7f], 0), "140733498785828"),
+		("194,tempminmax#1", entry(100, 100, [36, 0, 20, 45, 0, 0], 0), "36.0°C"),
+		("194,tempminmax#5", entry(100, 100, [36, 0, 20, 45, 0, 0], 0), "36°C (min: 20°C, max: 45°C, over limit: 0)"),
+	];
+
+	for (description, data, rendered) in table.iter() {
+		let attr = vendor_attribute::parse(description).unwrap();
+		assert_eq!(Raw::from_raw_entry(data, &Some(attr)).to_string(), *rendered, "{}", description);
+	}
+	```
+	*/
 	pub fn from_raw_entry(data: &[u8], attr: &Option<drivedb::Attribute>) -> Self {
 		let (fmt, byte_order) = attr.as_ref().map(|a|
 			(a.format.clone(), a.byte_order.clone())
 		).unwrap_or(
 			("raw48".to_string(), "543210".to_string())
 		);
-		let raw48 = reorder(&data, &byte_order);
-		let raw64 = reorder(&data, &format!("{:_>8}", byte_order));
+		let rawvalue = raw_value(data, &byte_order);
+
+		// lower 48 bits of the value, least significant byte/word first, just like `raw[]` and `word[]` in smartmontools
+		let raw: [u8; 6] = [
+			rawvalue as u8,
+			(rawvalue >> 8) as u8,
+			(rawvalue >> 16) as u8,
+			(rawvalue >> 24) as u8,
+			(rawvalue >> 32) as u8,
+			(rawvalue >> 40) as u8,
+		];
+		let word: [u16; 3] = [
+			rawvalue as u16,
+			(rawvalue >> 16) as u16,
+			(rawvalue >> 32) as u16,
+		];
+
+		let (fmt, layout) = match fmt.split_once('#') {
+			Some((fmt, layout)) => (fmt, layout.parse::<u8>().ok()),
+			None => (fmt.as_ref(), None),
+		};
 
 		use self::Raw::*;
-		match fmt.as_ref() {
-			"raw8" => Raw8(raw48),
-			"raw16" => Raw16(
-				raw48.chunks(2).map(|i| read(i, 16) as u16).collect()
-			),
-			"raw56" | "hex56" => Raw64(read(&raw64[1..8], 56)),
-			"raw64" | "hex64" => Raw64(read(&raw64, 64)),
-			"raw16(avg16)" => {
-				let mut raw48 = raw48.chunks(2).map(|i| read(i, 16) as u16);
-				Raw16avg16 {
-					value: raw48.next().unwrap(), // the 0th element should always be here
-					average: raw48.next().unwrap(), // and so is the 1st
-				}
-			},
-			"raw16(raw16)" => {
-				let mut raw48 = raw48.chunks(2).map(|i| read(i, 16) as u16);
-				let x = raw48.next().unwrap(); // 0th element should always be here
-				let opt: Vec<u16> = raw48.collect();
-				Raw16opt16(x, opt.iter().filter(|&&i| i>0).max().map(|_| opt.clone()))
-			},
-			"raw24(raw8)" => {
-				let x = read(&raw48[3..6], 24) as u32;
-				let opt: Vec<u8> = raw48.iter().take(3).map(|&i|i).collect();
-				Raw24opt8(x, opt.iter().filter(|&&i| i>0).max().map(|_| opt.clone()))
+		match fmt {
+			"raw8" => Raw8(raw.iter().rev().copied().collect()),
+			"raw16" => Raw16(word.iter().rev().copied().collect()),
+			"hex48" => Hex(rawvalue, 48),
+			"hex56" => Hex(rawvalue, 56),
+			"hex64" => Hex(rawvalue, 64),
+			"raw16(avg16)" => Raw16avg16 {
+				value: word[0],
+				average: word[1],
 			},
+			"raw16(raw16)" => Raw16opt16(
+				word[0],
+				if word[1] != 0 || word[2] != 0 { Some(vec![word[2], word[1]]) } else { None },
+			),
+			"raw24(raw8)" => Raw24opt8(
+				(rawvalue & 0xff_ffff) as u32,
+				if raw[3] != 0 || raw[4] != 0 || raw[5] != 0 { Some(vec![raw[5], raw[4], raw[3]]) } else { None },
+			),
 			"raw24/raw24" => Raw24div(
-				read(&raw48[0..3], 24) as u32,
-				read(&raw48[3..6], 24) as u32,
+				(rawvalue >> 24) as u32,
+				(rawvalue & 0xff_ffff) as u32,
 			),
 			"raw24/raw32" => Raw24div(
-				read(&raw64[1..4], 24) as u32,
-				read(&raw64[4..8], 32) as u32,
+				(rawvalue >> 32) as u32,
+				(rawvalue & 0xffff_ffff) as u32,
 			),
-			"sec2hour" => Seconds(read(&raw48, 48)),
-			"min2hour" => Minutes(read(&raw48, 48)),
-			"halfmin2hour" => Seconds(read(&raw48, 48) * 30),
-			"msec24hour32" => HoursMilliseconds(
-				read(&raw64[4..8], 32) as u32, // hour
-				read(&raw64[1..4], 24) as u32, // msec
+			"sec2hour" => Seconds(rawvalue),
+			// only the lower 32 bits hold minutes
+			"min2hour" => Minutes(
+				((word[1] as u64) << 16) + word[0] as u64,
+				if word[2] != 0 { Some(word[2]) } else { None },
 			),
-			"temp10x" => Celsius(
-				read(&raw48[4..6], 16) as f32 / 10.
+			"halfmin2hour" => Seconds(rawvalue * 30),
+			"msec24hour32" => HoursMilliseconds(
+				(rawvalue & 0xffff_ffff) as u32, // hours
+				(rawvalue >> 32) as u32, // msec
 			),
-			"tempminmax" => {
-				/*
-				This is a chart of all possible raw value interpretations for attributes of type 'tempminmax':
-				> [5][4][3][2][1][0] raw[]
-				> [ 2 ] [ 1 ] [ 0 ]  word[]
-				> xx HH xx LL xx TT (Hitachi/HGST)
-				> xx LL xx HH xx TT (Kingston SSDs)
-				> 00 00 HH LL xx TT (Maxtor, Samsung, Seagate, Toshiba)
-				> 00 00 00 HH LL TT (WDC)
-				> CC CC HH LL xx TT (WDC, CCCC=over temperature count)
-				> (xx = 00/ff, possibly sign extension of lower byte)
-
-				However, smartmontools itself looks for slightly generalized patterns, in order:
-				00 00 00 00 xx TT
-				00 00 HL LH xx TT
-				00 00 00 HL LH TT
-				xx HL xx LH xx TT
-				CC CC HL LH xx TT
-
-				TODO? negative temperatures (see e.g. https://www.smartmontools.org/ticket/291)
-				TODO? we're also skipping WDC overheating counters here
-				*/
-
-				match raw48[..] {
-					[0, 0, 0, 0, 0, t] => Celsius(t as f32),
-					[0, 0, 0, x, y, t] => CelsiusMinMax {
-						current: t,
-						min: min(x, y),
-						max: max(x, y),
-					},
-					[0, 0, x, y, 0, t] => CelsiusMinMax {
-						current: t,
-						min: min(x, y),
-						max: max(x, y),
-					},
-					[0, x, 0, y, 0, t] => CelsiusMinMax {
-						current: t,
-						min: min(x, y),
-						max: max(x, y),
-					},
-					// whatever this might be, show it using default formatter
-					_ => Raw64( read(&raw48, 48) ),
-				}
-			},
-			// {raw,hex}48 is the default
-			_ => Raw64( read(&raw48, 48) ),
+			"temp10x" => Celsius(word[0] as f32 / 10.),
+			// whatever this might be if it does not match any of the known layouts, show it using default formatter
+			"tempminmax" => tempminmax(&raw, &word, layout).unwrap_or(Raw64(rawvalue)),
+			// raw{48,56,64} is the default
+			_ => Raw64(rawvalue),
 		}
-
 	}
 }
@@ -0,0 +1,37 @@
+/**
+Device capacity, as seen from different angles.
+
+The capacity that the device reports in IDENTIFY DEVICE might be smaller than what the device is capable of, as it can be clipped by:
+
+- SET MAX ADDRESS (Host Protected Area feature set), or SET ACCESSIBLE MAX ADDRESS (Accessible Max Address Configuration feature set, which supersedes HPA), which clip IDENTIFY DEVICE capacity below native max address, or
+- DEVICE CONFIGURATION SET (Device Configuration Overlay feature set), which clips native max address itself.
+
+All values are in logical sectors.
+*/
+#[derive(Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Capacity {
+	/// number of sectors accessible to the host (IDENTIFY DEVICE)
+	pub accessible: u64,
+	/// native number of sectors (READ NATIVE MAX ADDRESS (EXT) or GET NATIVE MAX ADDRESS EXT), if available
+	pub native: Option<u64>,
+	/// number of sectors the device would have if it was not restricted by DCO (DEVICE CONFIGURATION IDENTIFY), if available
+	pub dco: Option<u64>,
+}
+
+impl Capacity {
+	/// Whether accessible capacity is clipped by HPA or Accessible Max Address
+	pub fn is_clipped(&self) -> bool {
+		self.native.map(|native| native > self.accessible).unwrap_or(false)
+	}
+
+	/// Whether native capacity is clipped by DCO
+	pub fn is_dco_restricted(&self) -> bool {
+		match (self.native, self.dco) {
+			(Some(native), Some(dco)) => dco > native,
+			// DEVICE CONFIGURATION IDENTIFY succeeded but READ NATIVE MAX ADDRESS did not: still better than nothing
+			(None, Some(dco)) => dco > self.accessible,
+			_ => false,
+		}
+	}
+}
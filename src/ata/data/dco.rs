@@ -0,0 +1,55 @@
+use super::is_set;
+
+/// DEVICE CONFIGURATION IDENTIFY data structure (see ATA8-ACS, 7.14.4)
+///
+/// Only lists features and capabilities that Device Configuration Overlay allows to be hidden from the host.
+#[derive(Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct DeviceConfiguration {
+	pub revision: u16,
+
+	/// bitmask of supported Multiword DMA modes, bit 0 for mode 0 etc.
+	pub mwdma_modes: u8,
+	/// bitmask of supported Ultra DMA modes, bit 0 for mode 0 etc.
+	pub udma_modes: u8,
+
+	/// maximum LBA the device can report if DCO was not used to restrict it
+	pub max_lba: u64,
+
+	pub smart: bool,
+	pub smart_self_test: bool,
+	pub smart_error_logging: bool,
+	pub security: bool,
+	pub power_up_in_standby: bool,
+	pub read_write_dma_queued: bool,
+	pub aam: bool,
+	pub hpa: bool,
+	pub lba48: bool,
+}
+
+pub fn parse_dco(data: &Vec<u8>) -> Option<DeviceConfiguration> {
+	if data.len() < 512 { return None; }
+	let data = crate::utils::bytes_to_be_words(data);
+
+	Some(DeviceConfiguration {
+		revision: data[0],
+
+		mwdma_modes: (data[1] & 0b111) as u8,
+		udma_modes: (data[2] & 0b111_1111) as u8,
+
+		max_lba: ((data[6] as u64) << 48)
+		       + ((data[5] as u64) << 32)
+		       + ((data[4] as u64) << 16)
+		       +  (data[3] as u64),
+
+		smart: is_set(data[7], 0),
+		smart_self_test: is_set(data[7], 1),
+		smart_error_logging: is_set(data[7], 2),
+		security: is_set(data[7], 3),
+		power_up_in_standby: is_set(data[7], 4),
+		read_write_dma_queued: is_set(data[7], 5),
+		aam: is_set(data[7], 6),
+		hpa: is_set(data[7], 7),
+		lba48: is_set(data[7], 8),
+	})
+}
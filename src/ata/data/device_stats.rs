@@ -0,0 +1,153 @@
+/*!
+Device Statistics log (log address 0x04), see ACS-3, 9.5.
+
+Every statistic is a little-endian qword: bit 63 tells whether the device supports the statistic, bit 62 tells whether its value is valid, and the value itself takes the lower bits (up to 48 of them, depending on the statistic).
+
+Only statistics that are of some general interest (and that have more or less common equivalents among SMART attributes) are decoded.
+*/
+
+use byteorder::{ReadBytesExt, LittleEndian};
+
+/// Log address to use with READ LOG EXT
+pub const LOG_ADDRESS: u8 = 0x04;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+	List = 0x00,
+	General = 0x01,
+	FreeFall = 0x02,
+	RotatingMedia = 0x03,
+	GeneralErrors = 0x04,
+	Temperature = 0x05,
+	Transport = 0x06,
+	SolidState = 0x07,
+}
+
+fn qword(data: &[u8], offset: usize) -> u64 {
+	(&data[offset .. offset + 8]).read_u64::<LittleEndian>().unwrap()
+}
+
+// statistic is only worth anything if it is both supported (bit 63) and valid (bit 62)
+fn statistic(data: &[u8], offset: usize) -> Option<u64> {
+	let q = qword(data, offset);
+	if q & (0b11 << 62) == (0b11 << 62) {
+		Some(q & 0xffff_ffff_ffff)
+	} else { None }
+}
+
+// every page except the list of supported pages starts with the header: revision number in bits 15:0, page number in bits 23:16
+fn check_header(data: &[u8], page: Page) -> bool {
+	if data.len() < 512 { return false; }
+	let header = qword(data, 0);
+	header & 0xffff != 0 && (header >> 16) & 0xff == page as u64
+}
+
+/// Parses the list of supported pages (page 0x00).
+pub fn parse_list(data: &[u8]) -> Option<Vec<u8>> {
+	if data.len() < 512 { return None; }
+
+	// byte 8 is the number of entries, and entries themselves follow
+	let count = data[8] as usize;
+	Some(data[9 .. 9 + count.min(512 - 9)].to_vec())
+}
+
+/// General Statistics page (0x01)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct General {
+	pub power_on_resets: Option<u64>,
+	pub power_on_hours: Option<u64>,
+	pub logical_sectors_written: Option<u64>,
+	pub write_commands: Option<u64>,
+	pub logical_sectors_read: Option<u64>,
+	pub read_commands: Option<u64>,
+}
+
+pub fn parse_general(data: &[u8]) -> Option<General> {
+	if !check_header(data, Page::General) { return None; }
+
+	Some(General {
+		power_on_resets: statistic(data, 8),
+		power_on_hours: statistic(data, 16).map(|x| x & 0xffff_ffff),
+		logical_sectors_written: statistic(data, 24),
+		write_commands: statistic(data, 32),
+		logical_sectors_read: statistic(data, 40),
+		read_commands: statistic(data, 48),
+	})
+}
+
+/// Rotating Media Statistics page (0x03)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct RotatingMedia {
+	pub spindle_motor_power_on_hours: Option<u64>,
+	pub head_flying_hours: Option<u64>,
+	pub head_load_events: Option<u64>,
+	pub reallocated_sectors: Option<u64>,
+	/// number of logical sectors that are going to be reallocated (cf. Current_Pending_Sector SMART attribute)
+	pub reallocation_candidates: Option<u64>,
+}
+
+pub fn parse_rotating_media(data: &[u8]) -> Option<RotatingMedia> {
+	if !check_header(data, Page::RotatingMedia) { return None; }
+
+	Some(RotatingMedia {
+		spindle_motor_power_on_hours: statistic(data, 8).map(|x| x & 0xffff_ffff),
+		head_flying_hours: statistic(data, 16).map(|x| x & 0xffff_ffff),
+		head_load_events: statistic(data, 24),
+		reallocated_sectors: statistic(data, 32).map(|x| x & 0xffff_ffff),
+		reallocation_candidates: statistic(data, 56).map(|x| x & 0xffff_ffff),
+	})
+}
+
+/// Temperature Statistics page (0x05); all the values are in °C
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Temperature {
+	pub current: Option<i8>,
+	/// highest temperature during the lifetime of the device
+	pub highest: Option<i8>,
+	/// lowest temperature during the lifetime of the device
+	pub lowest: Option<i8>,
+}
+
+pub fn parse_temperature(data: &[u8]) -> Option<Temperature> {
+	if !check_header(data, Page::Temperature) { return None; }
+
+	// temperatures are signed bytes
+	let temperature = |offset| statistic(data, offset).map(|x| x as u8 as i8);
+
+	Some(Temperature {
+		current: temperature(8),
+		highest: temperature(32),
+		lowest: temperature(40),
+	})
+}
+
+/// Solid State Device Statistics page (0x07)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SolidState {
+	/// vendor's estimate of the percentage of device life used, based on the actual usage and on the manufacturer's prediction of device life; might exceed 100
+	pub percentage_used: Option<u8>,
+}
+
+pub fn parse_solid_state(data: &[u8]) -> Option<SolidState> {
+	if !check_header(data, Page::SolidState) { return None; }
+
+	Some(SolidState {
+		percentage_used: statistic(data, 8).map(|x| x as u8),
+	})
+}
+
+/// Decoded pages of the Device Statistics log; pages that are not supported by the device (or cannot be read) are `None`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct DeviceStatistics {
+	/// list of supported pages, as reported by the device
+	pub pages: Vec<u8>,
+	pub general: Option<General>,
+	pub rotating_media: Option<RotatingMedia>,
+	pub temperature: Option<Temperature>,
+	pub solid_state: Option<SolidState>,
+}
@@ -1,7 +1,9 @@
 use std::fmt;
 
+use super::is_set;
+
 // ATA strings are ASCII in range 0x20..=0x7e; replace anything else with space.
-fn read_string(arr: &Vec<u16>, start: usize, fin: usize) -> String {
+pub(crate) fn read_string(arr: &Vec<u16>, start: usize, fin: usize) -> String {
 	let mut output = String::with_capacity((fin - start) * 2);
 	let mut push_byte = |out: &mut String, b: u8| {
 		if (0x20..=0x7e).contains(&b) {
@@ -81,6 +83,16 @@ fn sata_speed_from_word(word: u16) -> Option<&'static str> {
 	None
 }
 
+// unlike w76, w77 reports current speed as a value, not as a bit mask
+fn sata_speed_current_from_word77(word77: u16) -> Option<&'static str> {
+	match (word77 >> 1) & 0b111 {
+		1 => Some("1.5 Gb/s"),
+		2 => Some("3.0 Gb/s"),
+		3 => Some("6.0 Gb/s"),
+		_ => None,
+	}
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 #[cfg_attr(feature = "serializable", derive(Serialize))]
 pub enum Ternary {
@@ -97,13 +109,246 @@ impl fmt::Display for Ternary {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serializable", derive(Serialize))]
 pub enum RPM {
 	Unknown, NonRotating, RPM(u16)
 }
 
-#[derive(Debug)]
+/// Nominal form factor, word 168
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum FormFactor {
+	Inch5_25, Inch3_5, Inch2_5, Inch1_8, LessThanInch1_8, MSATA, M2, MicroSSD, CFast,
+}
+
+impl fmt::Display for FormFactor {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", match self {
+			FormFactor::Inch5_25 => "5.25 inches",
+			FormFactor::Inch3_5 => "3.5 inches",
+			FormFactor::Inch2_5 => "2.5 inches",
+			FormFactor::Inch1_8 => "1.8 inches",
+			FormFactor::LessThanInch1_8 => "< 1.8 inches",
+			FormFactor::MSATA => "mSATA",
+			FormFactor::M2 => "M.2",
+			FormFactor::MicroSSD => "MicroSSD",
+			FormFactor::CFast => "CFast",
+		})
+	}
+}
+
+/// Zoned capabilities, word 69 bits 1:0
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Zoned {
+	HostAware, DeviceManaged,
+}
+
+impl fmt::Display for Zoned {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Zoned::HostAware => write!(f, "host aware"),
+			Zoned::DeviceManaged => write!(f, "device managed"),
+		}
+	}
+}
+
+/// Specific configuration, word 2
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SpecificConfiguration {
+	/// Device requires SET FEATURES subcommand to spin up after power-up (see Power-Up In Standby feature set)
+	pub spin_up_required: bool,
+	/// IDENTIFY DEVICE response is incomplete
+	pub incomplete: bool,
+}
+
+/// Serial ATA capabilities and features, words 76–79
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SataCapabilities {
+	pub link_speeds: Vec<&'static str>, // all supported signaling speeds, from the slowest
+	pub ncq: bool, // Native Command Queuing
+	pub ncq_priority: bool,
+	pub phy_event_counters: bool,
+	pub unload_while_ncq_outstanding: bool,
+	pub read_log_dma_ext: bool, // READ LOG DMA EXT is equivalent to READ LOG EXT
+	pub ncq_streaming: bool,
+	pub ncq_management: bool, // NCQ NON-DATA command
+	pub ncq_send_receive: bool, // SEND/RECEIVE FPDMA QUEUED commands
+	pub ncq_autosense: bool,
+	pub non_zero_buffer_offsets: Ternary,
+	pub dma_setup_auto_activation: Ternary,
+	pub in_order_data_delivery: Ternary,
+	pub software_settings_preservation: Ternary,
+
+	// link power management
+	pub hipm: bool, // Host-Initiated Interface Power Management
+	pub dipm: Ternary, // Device-Initiated Interface Power Management
+	pub host_automatic_partial_to_slumber: bool,
+	pub device_automatic_partial_to_slumber: Ternary,
+	pub devsleep: Ternary, // Device Sleep
+	pub devsleep_to_reduced_power_state: bool,
+}
+
+fn parse_sata_capabilities(data: &Vec<u16>) -> Option<SataCapabilities> {
+	// words 76–79 are not valid for PATA devices
+	if data[76] == 0x0000 || data[76] == 0xffff { return None; }
+
+	Some(SataCapabilities {
+		link_speeds: [(1, "1.5 Gb/s"), (2, "3.0 Gb/s"), (3, "6.0 Gb/s")].iter()
+			.filter(|&&(bit, _)| is_set(data[76], bit))
+			.map(|&(_, speed)| speed)
+			.collect(),
+		ncq: is_set(data[76], 8),
+		ncq_priority: is_set(data[76], 12),
+		phy_event_counters: is_set(data[76], 10),
+		unload_while_ncq_outstanding: is_set(data[76], 11),
+		read_log_dma_ext: is_set(data[76], 15),
+		ncq_streaming: is_set(data[77], 4),
+		ncq_management: is_set(data[77], 5),
+		ncq_send_receive: is_set(data[77], 6),
+		ncq_autosense: is_set(data[78], 7),
+		non_zero_buffer_offsets: make_ternary(data, 78, 1, 79, 1),
+		dma_setup_auto_activation: make_ternary(data, 78, 2, 79, 2),
+		in_order_data_delivery: make_ternary(data, 78, 4, 79, 4),
+		software_settings_preservation: make_ternary(data, 78, 6, 79, 6),
+
+		hipm: is_set(data[76], 9),
+		dipm: make_ternary(data, 78, 3, 79, 3),
+		host_automatic_partial_to_slumber: is_set(data[76], 13),
+		// there's no separate 'supported' bit for the device-initiated transitions in w78, w76:14 is used instead
+		device_automatic_partial_to_slumber: make_ternary(data, 76, 14, 79, 7),
+		devsleep: make_ternary(data, 78, 8, 79, 8),
+		devsleep_to_reduced_power_state: is_set(data[77], 7),
+	})
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum SecurityLevel {
+	/// Master password can be used to unlock the device
+	High,
+	/// Master password can only be used to erase the device
+	Maximum,
+}
+
+/// Security feature set status, see ATA8-ACS, 4.18, and IDENTIFY DEVICE words 89, 90, 92, 128.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SecurityStatus {
+	/// User password is set (i.e. the device will be locked on power-up)
+	pub enabled: bool,
+	pub locked: bool,
+	/// SECURITY FREEZE LOCK was issued (by BIOS, OS, or someone else), other security commands are rejected until power cycle
+	pub frozen: bool,
+	/// Too many wrong passwords were tried, device rejects further attempts until power cycle
+	pub count_expired: bool,
+	pub enhanced_erase_supported: bool,
+	pub level: SecurityLevel,
+	pub master_password_id: Option<u16>,
+	/// Estimated SECURITY ERASE UNIT completion time
+	pub erase_time: Option<EraseTime>,
+	/// Estimated SECURITY ERASE UNIT completion time for the enhanced mode
+	pub enhanced_erase_time: Option<EraseTime>,
+}
+
+/// Estimated SECURITY ERASE UNIT completion time, in minutes
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum EraseTime {
+	Minutes(u32),
+	/// the largest value that can be reported: erase takes more than that
+	MoreThan(u32),
+}
+
+impl SecurityStatus {
+	/// Returns the number of security state as seen in ATA8-ACS, 4.18.11 (e.g. `4` for SEC4, locked device).
+	pub fn state(&self) -> u8 {
+		match (self.enabled, self.locked, self.frozen) {
+			(false, _, false) => 1,
+			(false, _, true) => 2,
+			(true, true, _) => 4,
+			(true, false, false) => 5,
+			(true, false, true) => 6,
+		}
+	}
+}
+
+// w217; also used in Identify Device Data log
+pub(crate) fn parse_rpm(word: u16) -> RPM {
+	match word {
+		// all values except 0x0000 are reserved (TODO warning?)
+		0x0000 | 0xffff | 0x0002..=0x0400 => RPM::Unknown,
+		0x0001 => RPM::NonRotating,
+		i => RPM::RPM(i),
+	}
+}
+
+// w168; also used in Identify Device Data log
+pub(crate) fn parse_form_factor(word: u16) -> Option<FormFactor> {
+	match word & 0xf {
+		1 => Some(FormFactor::Inch5_25),
+		2 => Some(FormFactor::Inch3_5),
+		3 => Some(FormFactor::Inch2_5),
+		4 => Some(FormFactor::Inch1_8),
+		5 => Some(FormFactor::LessThanInch1_8),
+		6 => Some(FormFactor::MSATA),
+		7 => Some(FormFactor::M2),
+		8 => Some(FormFactor::MicroSSD),
+		9 => Some(FormFactor::CFast),
+		_ => None, // not reported, or reserved
+	}
+}
+
+// w69 bits 1:0; also used in Identify Device Data log
+pub(crate) fn parse_zoned(word: u16) -> Option<Zoned> {
+	match word & 0b11 {
+		0b01 => Some(Zoned::HostAware),
+		0b10 => Some(Zoned::DeviceManaged),
+		_ => None, // not reported, or reserved
+	}
+}
+
+// w89, w90; also used in Identify Device Data log
+pub(crate) fn erase_time(word: u16) -> Option<EraseTime> {
+	let (time, max) = if is_set(word, 15) {
+		// ACS-3 extended format
+		(word & 0x7fff, 0x7fff)
+	} else {
+		(word & 0xff, 0xff)
+	};
+	match time {
+		0 => None, // not reported
+		// e.g. 0xff means 'more than 508 minutes'
+		time if time == max => Some(EraseTime::MoreThan((time as u32 - 1) * 2)),
+		time => Some(EraseTime::Minutes(time as u32 * 2)),
+	}
+}
+
+fn parse_security_status(data: &Vec<u16>) -> Option<SecurityStatus> {
+	let w128 = data[128];
+	if !is_set(w128, 0) { return None; }
+
+	Some(SecurityStatus {
+		enabled: is_set(w128, 1),
+		locked: is_set(w128, 2),
+		frozen: is_set(w128, 3),
+		count_expired: is_set(w128, 4),
+		enhanced_erase_supported: is_set(w128, 5),
+		level: if is_set(w128, 8) { SecurityLevel::Maximum } else { SecurityLevel::High },
+		master_password_id: match data[92] {
+			// not supported
+			0x0000 | 0xffff => None,
+			id => Some(id),
+		},
+		erase_time: erase_time(data[89]),
+		enhanced_erase_time: if is_set(w128, 5) { erase_time(data[90]) } else { None },
+	})
+}
+
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serializable", derive(Serialize))]
 pub struct IdCommands {
 	pub device_reset: bool,
@@ -120,21 +365,28 @@ pub struct IdCommands {
 	pub read_write_dma_ext_gpl: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serializable", derive(Serialize))]
 pub struct Id {
 	pub is_ata: bool, // probably redundant
 	pub incomplete: bool, // content of words other that 0 or 2 might be invalid
+	pub cfa: bool, // CompactFlash Association feature set
+	pub specific_configuration: Option<SpecificConfiguration>,
+	pub checksum_valid: Option<bool>, // word 255 integrity check; `None` if the device does not provide one
 
 	pub serial: String,
 	pub firmware: String,
 	pub model: String,
 
 	pub capacity: u64,
+	pub sectors: u64, // user addressable logical sectors
 	pub sector_size_phy: u32,
 	pub sector_size_log: u32,
 
 	pub rpm: RPM,
+	pub form_factor: Option<FormFactor>,
+	pub zoned: Option<Zoned>,
+	pub media_serial: Option<String>, // only available if media is present
 
 	pub trusted_computing_supported: bool,
 
@@ -147,9 +399,19 @@ pub struct Id {
 	pub trim_zeroed: bool,
 	pub dsn_available: bool,
 	pub dsn_enabled: bool,
+	pub sense_data_reporting: Ternary,
 	pub sct_feature_control_supported: bool,
+	pub sata_capabilities: Option<SataCapabilities>, // only available for SATA devices
+	pub queue_depth: Option<u8>, // only available if NCQ is supported
+	pub multiple_sectors_max: Option<u8>, // READ/WRITE MULTIPLE support
+	pub multiple_sectors_current: Option<u8>,
+	pub iordy_supported: bool,
+	pub iordy_can_be_disabled: bool,
+	pub standby_timer_vendor_specific: bool, // Standby timer values are vendor-specific rather than defined by the standard
+	pub standby_timer_has_minimum: bool, // device has a minimum Standby timer value that is device-specific
 	pub security_state: u16,
 	pub security_master_pw_id: u16,
+	pub security_status: Option<SecurityStatus>, // only available if Security feature set is supported
 
 	pub commands_supported: IdCommands,
 
@@ -157,8 +419,14 @@ pub struct Id {
 	pub write_cache: Ternary,
 	pub read_look_ahead: Ternary,
 	pub hpa: Ternary, // Host Protected Area
+	pub lba48_supported: bool, // 48-bit Address feature set
+	pub dco_supported: bool, // Device Configuration Overlay
+	pub ama_supported: bool, // Accessible Max Address Configuration
 	pub apm: Ternary, // Advanced Power Management
+	pub apm_level: Option<u8>, // only available if APM is enabled
 	pub aam: Ternary, // Automatic Acoustic Management
+	pub aam_level: Option<u8>, // only available if AAM is enabled
+	pub aam_recommended_level: Option<u8>, // vendor's recommended AAM level
 	pub gp_logging_supported: bool, // General Purpose Logging
 	pub wwn_supported: bool, // World Wide Name
 	pub security: Ternary,
@@ -168,9 +436,6 @@ pub struct Id {
 	pub smart_self_test_supported: bool,
 }
 
-fn is_set(word: u16, bit: usize) -> bool {
-	word & (1<<bit) != 0
-}
 fn make_ternary(data: &Vec<u16>, word_sup: usize, bit_sup: usize, word_enabled: usize, bit_enabled: usize) -> Ternary {
 	if !is_set(data[word_sup], bit_sup) {
 		Ternary::Unsupported
@@ -180,8 +445,30 @@ fn make_ternary(data: &Vec<u16>, word_sup: usize, bit_sup: usize, word_enabled:
 	}
 }
 
+/**
+Swaps bytes of serial number, firmware revision and model strings in raw IDENTIFY DEVICE `data`, to be parsed with `parse_id()` afterwards.
+
+Some USB bridges mangle these strings this way; see `drivedb::FirmwareBug::SwapId`. Note that this breaks the integrity checksum, if there's one.
+*/
+pub fn swap_strings(data: &mut [u8]) {
+	// words 10–19, 23–26 and 27–46
+	for range in [20 .. 40, 46 .. 94].iter() {
+		if let Some(bytes) = data.get_mut(range.clone()) {
+			for pair in bytes.chunks_exact_mut(2) {
+				pair.swap(0, 1);
+			}
+		}
+	}
+}
+
 pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 	if data.len() < 512 { return None; }
+
+	// w255: if bits 7:0 contain the signature (0xa5), bits 15:8 are set so that all 512 bytes add up to zero
+	let checksum_valid = if data[510] == 0xa5 {
+		Some(data[.. 512].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0)
+	} else { None };
+
 	let data = crate::utils::bytes_to_be_words(data);
 	/*
 	TODO ATA8-ACS T13/1699-D Revision 3f field description
@@ -189,23 +476,14 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 		vs crap knows what other revisions and standards
 
 	TODO
-	w0       if 0x848a, CFA feature set is supported, and `is_ata`, `incomplete` are irrelevant
-	w2       values regarding incomplete id output
-	w47      READ/WRITE MULTIPLE support
-	w49:13   standby timer values: 1 if standard compliant, 0 if vendor-specific
-	w49:11   IODRY is supported
-	w49:10   IODRY can (1) or can not (0) be disabled
 	w49:9    LBA transition is supported
 	w49:8    DMA is supported
-	w50:0    device has a minimum Standby timer value that is device-specific
-	w59:8    if 1, w59:7..0 reflects the number of logical sectors currently set to transfer on READ/WRITE MULTIPLE command
 	w63      Multiword DMA transfer modes
 	w64      PIO transfer modes
 	w65      minimum Multiword DMA transfer cycle time per word
 	w66      device recommended Multiword DMA cycle time
 	w67      minimum PIO transfer cycle time without IORDY flow control
 	w67      minimum PIO transfer cycle time with IORDY flow control
-	w75      queue depth
 
 	w82:4    PACKET feature set is supported
 	w85:4    PACKET feature set is supported
@@ -216,9 +494,6 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 	w82:8    SERVICE interrupt is supported
 	w85:8    SERVICE interrupt is enabled
 
-	w83:2    CFA feature set is supported
-	w86:2    CFA feature set is supported
-
 	w83:5    power-up in standby is supported
 	w86:5    power-up in standby is enabled
 
@@ -231,13 +506,6 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 	w83:8    SET MAX security extension is supported
 	w86:8    SET MAX security extension enabled
 
-	w83:10   48-bit Address feature set is supported
-	w86:10   48-bit Address feature set is supported
-
-	w83:11   Device Configuration Overlay feature set is supported
-	w86:11   Device Configuration Overlay feature set is supported
-
-	w84:2    media serial number field is supported (words 176-205)
 	w84:3    Media Card Pass Through Command feature set is supported
 	w84:4    Streaming feature set is supported
 
@@ -252,17 +520,11 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 
 	w85:3    mandatory Power Management feature set is supported
 
-	w87:2    media is present and media serial number in words 176-205 is valid
 	w87:3    the Media Card Pass Through feature set is enabled
 	w87:5    the device supports the General Purpose Logging feature set
 
 	w88       Ultra DMA transfer modes, supported and currently selected
-	w89       time required for Security erase unit completion
-	w90       time required for Enhanced security erase unit completion
-	w91       Advanced power management level value
-	w92       Master Password Identifier
 	w93       Hardware configuration test results
-	w94       Current automatic acoustic management value
 	w95       Stream Minimum Request Size
 	w96       Streaming Transfer Time - DMA
 	w97       Streaming Access Latency - DMA and PIO
@@ -270,9 +532,7 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 	w104      streaming Transfer Time - PIO
 	w107      Inter-seek delay for ISO 7779 standard acoustic testing
 	w108-111  World wide name
-	w128      Security status
 	w160      CFA power mode
-	w176-205  Current media serial number
 	w206      SCT Command Transport
 	w209      Alignment of logical blocks within a physical block
 	w210-211  Write-Read-Verify Sector Count Mode 3 Only
@@ -283,7 +543,6 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 	w220      Write-Read-Verify Mode
 	w222      Transport major revision number
 	w223      Transport minor revision number
-	w255      Integrity word (optional)
 
 	XXX what about constant fields? E.g.:
 	> Bit 15 of word 50 shall be cleared to zero to indicate that the contents of word 50 are valid.
@@ -325,15 +584,42 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 		512
 	};
 
+	let sectors = if sectors_48bit > 0 { sectors_48bit } else { sectors };
+
+	let apm = make_ternary(&data, 83, 3, 86, 3);
+	let aam = make_ternary(&data, 83, 9, 86, 9);
+
+	let specific_configuration = match data[2] {
+		0x37c8 => Some(SpecificConfiguration { spin_up_required: true, incomplete: true }),
+		0x738c => Some(SpecificConfiguration { spin_up_required: true, incomplete: false }),
+		0x8c73 => Some(SpecificConfiguration { spin_up_required: false, incomplete: true }),
+		0xc837 => Some(SpecificConfiguration { spin_up_required: false, incomplete: false }),
+		_ => None,
+	};
+
+	// w0 of CompactFlash devices has a magic value, and bits 15 and 2 do not mean what they usually do
+	let cfa_magic = data[0] == 0x848a;
+
+	let sata_capabilities = parse_sata_capabilities(&data);
+
+	// w50, w85–87 are only valid if bit 15 is 0 and bit 14 is 1
+	let w50_valid = data[50] & 0xc000 == 0x4000;
+	let w87_valid = data[87] & 0xc000 == 0x4000;
+
 	Some(Id {
-		is_ata: !is_set(data[0], 15),
-		incomplete: is_set(data[0], 2),
+		is_ata: cfa_magic || !is_set(data[0], 15),
+		incomplete: (!cfa_magic && is_set(data[0], 2))
+			|| specific_configuration.map(|c| c.incomplete).unwrap_or(false),
+		cfa: cfa_magic || is_set(data[83], 2),
+		specific_configuration: specific_configuration,
+		checksum_valid: checksum_valid,
 
 		serial: read_string(&data, 10, 19),
 		firmware: read_string(&data, 23, 26),
 		model: read_string(&data, 27, 46),
 
-		capacity: (sector_size_log as u64) * if sectors_48bit > 0 { sectors_48bit } else { sectors },
+		capacity: (sector_size_log as u64) * sectors,
+		sectors: sectors,
 
 		sector_size_phy: if sector_size_valid {
 			// bit 13 set to 1 indicates there's more than 1 logical sector per physical
@@ -345,12 +631,13 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 		} else { 512 },
 		sector_size_log: sector_size_log,
 
-		rpm: match data[217] {
-			// all values except 0x0000 are reserved (TODO warning?)
-			0x0000 | 0xffff | 0x0002..=0x0400 => RPM::Unknown,
-			0x0001 => RPM::NonRotating,
-			i => RPM::RPM(i),
-		},
+		rpm: parse_rpm(data[217]),
+		form_factor: parse_form_factor(data[168]),
+		zoned: parse_zoned(data[69]),
+		// words 176–195 hold media serial number, words 196–205 hold media manufacturer, which we're ignoring for now
+		media_serial: if is_set(data[84], 2) && w87_valid && is_set(data[87], 2) {
+			Some(read_string(&data, 176, 195))
+		} else { None },
 
 		trusted_computing_supported: is_set(data[48], 0),
 
@@ -450,8 +737,9 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 			}
 		},
 		sata_speed_max: sata_speed_from_word(data[76]),
-		sata_speed_current: sata_speed_from_word(data[77])
-			.or_else(|| sata_speed_from_word(data[78])),
+		sata_speed_current: if sata_capabilities.is_some() {
+			sata_speed_current_from_word77(data[77])
+		} else { None },
 		trim_supported: data[169] & 0b0000_0000_0000_0001 != 0,
 		// DRAT/RZAT flags live in word 69, not word 169.
 		trim_deterministic: data[69] & (1 << 14) != 0,
@@ -460,9 +748,32 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 			&& (data[119] & 0xc200) == 0x4200
 			&& (data[120] & 0xc000) == 0x4000,
 		dsn_enabled: (data[120] & 0x0200) != 0,
+		sense_data_reporting: if (data[119] & 0xc000) == 0x4000 {
+			make_ternary(&data, 119, 6, 120, 6)
+		} else { Ternary::Unsupported },
 		sct_feature_control_supported: (data[206] & 0x0010) != 0,
 		security_state: data[128],
 		security_master_pw_id: data[92],
+		security_status: parse_security_status(&data),
+		queue_depth: match &sata_capabilities {
+			// w75 bits 4:0 hold maximum queue depth minus one
+			Some(sata) if sata.ncq => Some((data[75] & 0x1f) as u8 + 1),
+			_ => None,
+		},
+		sata_capabilities: sata_capabilities,
+		// w47 bits 7:0 hold the maximum number of sectors per DRQ data block, zero means READ/WRITE MULTIPLE are not supported
+		multiple_sectors_max: match data[47] & 0xff {
+			0 => None,
+			n => Some(n as u8),
+		},
+		// w59 bit 8 tells whether bits 7:0 are valid
+		multiple_sectors_current: if is_set(data[59], 8) {
+			Some((data[59] & 0xff) as u8)
+		} else { None },
+		iordy_supported: is_set(data[49], 11),
+		iordy_can_be_disabled: is_set(data[49], 10),
+		standby_timer_vendor_specific: !is_set(data[49], 13),
+		standby_timer_has_minimum: w50_valid && is_set(data[50], 0),
 
 		commands_supported: IdCommands {
 			// XXX these, according to ATA8-ACS rev 62, should be mirrored in 'feature status' words
@@ -485,14 +796,24 @@ pub fn parse_id(data: &Vec<u8>) -> Option<Id> {
 		power_mgmt_supported: is_set(data[82], 3),
 		write_cache: make_ternary(&data, 82, 5, 85, 5),
 		read_look_ahead: make_ternary(&data, 82, 6, 85, 6),
-		/* TODO
-		> the device is not indicating its full size as defined by READ NATIVE MAX or READ NATIVE MAX EXT command
-		> because a SET MAX ADDESS or SET MAX ADDRESS EXT command has been issued to resize the device
-		which is indicated by hpa == Enabled
-		*/
+		// N.B. Enabled here does not mean that the capacity is actually clipped; use READ NATIVE MAX ADDRESS to find out (see `Misc::get_capacity()`)
 		hpa: make_ternary(&data, 82, 10, 85, 10),
-		apm: make_ternary(&data, 83, 3, 86, 3),
-		aam: make_ternary(&data, 83, 9, 86, 9),
+		lba48_supported: is_set(data[83], 10),
+		dco_supported: is_set(data[83], 11),
+		ama_supported: is_set(data[119], 8),
+		apm: apm,
+		// w91 bits 15..8 are reserved
+		apm_level: if apm == Ternary::Enabled {
+			Some((data[91] & 0xff) as u8)
+		} else { None },
+		aam: aam,
+		// w94 bits 15..8 are vendor's recommended value, bits 7..0 are the current one
+		aam_level: if aam == Ternary::Enabled {
+			Some((data[94] & 0xff) as u8)
+		} else { None },
+		aam_recommended_level: if aam != Ternary::Unsupported {
+			Some((data[94] >> 8) as u8)
+		} else { None },
 		gp_logging_supported: is_set(data[84], 5),
 		wwn_supported: is_set(data[84], 8), // XXX mirrored; see commands_supported
 		security: make_ternary(&data, 82, 1, 85, 1),
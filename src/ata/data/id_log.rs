@@ -0,0 +1,288 @@
+/*!
+Identify Device Data log (log address 0x30), see ACS-3, 9.10.
+
+This log duplicates a lot of IDENTIFY DEVICE data, but in a more precise and future-proof form: every field is a little-endian qword, most of them with bit 63 telling whether the rest of the field is valid. Use [`DeviceData::update_id()`](struct.DeviceData.html#method.update_id) to replace legacy IDENTIFY DEVICE values with the ones from this log.
+
+Only pages and fields that have some equivalent in [`Id`](../id/struct.Id.html), or are otherwise of interest, are decoded; the rest of some qwords is provided as is.
+*/
+
+use byteorder::{ReadBytesExt, LittleEndian};
+
+use super::id::{self, RPM, FormFactor, Zoned, SecurityStatus, SecurityLevel};
+
+/// Log address to use with READ LOG EXT
+pub const LOG_ADDRESS: u8 = 0x30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+	List = 0x00,
+	Copy = 0x01, // copy of IDENTIFY DEVICE data
+	Capacity = 0x02,
+	SupportedCapabilities = 0x03,
+	CurrentSettings = 0x04,
+	Strings = 0x05,
+	Security = 0x06,
+	ParallelATA = 0x07,
+	SerialATA = 0x08,
+}
+
+fn qword(data: &[u8], offset: usize) -> u64 {
+	(&data[offset .. offset + 8]).read_u64::<LittleEndian>().unwrap()
+}
+
+// most of the fields have bit 63 set to indicate that the rest of the qword is valid
+fn valid_qword(data: &[u8], offset: usize) -> Option<u64> {
+	let q = qword(data, offset);
+	if q & (1 << 63) != 0 {
+		Some(q & !(1 << 63))
+	} else { None }
+}
+
+// every page except the list of supported pages starts with the header: revision number in bits 15:0, page number in bits 23:16
+fn check_header(data: &[u8], page: Page) -> bool {
+	if data.len() < 512 { return false; }
+	let header = qword(data, 0);
+	header & 0xffff != 0 && (header >> 16) & 0xff == page as u64
+}
+
+/// Parses the list of supported pages (page 0x00).
+pub fn parse_list(data: &[u8]) -> Option<Vec<u8>> {
+	if data.len() < 512 { return None; }
+
+	// byte 8 is the number of entries, and entries themselves follow
+	let count = data[8] as usize;
+	Some(data[9 .. 9 + count.min(512 - 9)].to_vec())
+}
+
+/// Capacity page (0x02)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Capacity {
+	/// number of user addressable logical sectors
+	pub sectors: Option<u64>,
+	pub sector_size_log: Option<u32>,
+	pub sector_size_phy: Option<u32>,
+	/// offset of the first logical sector within the physical sector
+	pub logical_sector_offset: Option<u16>,
+	/// nominal buffer size in bytes
+	pub buffer_size: Option<u64>,
+}
+
+pub fn parse_capacity(data: &[u8]) -> Option<Capacity> {
+	if !check_header(data, Page::Capacity) { return None; }
+
+	let sector_size = valid_qword(data, 16);
+
+	// bit 61 tells whether LOGICAL SECTOR SIZE field is valid; if it is not, logical sector is 256 words long
+	let sector_size_log = sector_size.map(|q| match valid_qword(data, 24) {
+		// just like IDENTIFY DEVICE words 117–118, this is in words
+		Some(size) if q & (1 << 61) != 0 => (size & 0xffff_ffff) as u32 * 2,
+		_ => 512,
+	});
+
+	Some(Capacity {
+		sectors: valid_qword(data, 8).map(|q| q & 0xffff_ffff_ffff),
+		sector_size_log: sector_size_log,
+		// bit 62 tells whether there's more than one logical sector per physical, bits 19:16 is a power of 2 logical sectors per physical
+		sector_size_phy: sector_size.and_then(|q| sector_size_log.map(|log| {
+			if q & (1 << 62) != 0 {
+				log << ((q >> 16) & 0xf)
+			} else { log }
+		})),
+		logical_sector_offset: sector_size.map(|q| (q & 0xffff) as u16),
+		buffer_size: valid_qword(data, 32),
+	})
+}
+
+/// Supported Capabilities page (0x03)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SupportedCapabilities {
+	/// SUPPORTED CAPABILITIES field, as is
+	pub capabilities: Option<u64>,
+	pub rpm: Option<RPM>,
+	pub form_factor: Option<FormFactor>,
+	pub wwn: Option<u64>,
+	pub trim_supported: Option<bool>,
+	pub zoned: Option<Zoned>,
+}
+
+pub fn parse_supported_capabilities(data: &[u8]) -> Option<SupportedCapabilities> {
+	if !check_header(data, Page::SupportedCapabilities) { return None; }
+
+	Some(SupportedCapabilities {
+		capabilities: valid_qword(data, 8),
+		rpm: valid_qword(data, 24).map(|q| id::parse_rpm(q as u16)),
+		form_factor: valid_qword(data, 32).and_then(|q| id::parse_form_factor(q as u16)),
+		// WORLD WIDE NAME is a 128-bit field with bit 127 telling whether it is valid
+		wwn: if data[71] & 0x80 != 0 {
+			Some(qword(data, 56))
+		} else { None },
+		trim_supported: valid_qword(data, 72).map(|q| q & 1 != 0),
+		zoned: valid_qword(data, 96).and_then(|q| id::parse_zoned(q as u16)),
+	})
+}
+
+/// Current Settings page (0x04)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct CurrentSettings {
+	/// CURRENT SETTINGS field, as is
+	pub current_settings: Option<u64>,
+	/// FEATURE SETTINGS field, as is
+	pub feature_settings: Option<u64>,
+}
+
+pub fn parse_current_settings(data: &[u8]) -> Option<CurrentSettings> {
+	if !check_header(data, Page::CurrentSettings) { return None; }
+
+	Some(CurrentSettings {
+		current_settings: valid_qword(data, 8),
+		feature_settings: valid_qword(data, 16),
+	})
+}
+
+/// Strings page (0x05)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Strings {
+	pub serial: String,
+	pub firmware: String,
+	pub model: String,
+}
+
+pub fn parse_strings(data: &[u8]) -> Option<Strings> {
+	if !check_header(data, Page::Strings) { return None; }
+
+	// strings here are stored the same way they are in IDENTIFY DEVICE data, hence word offsets
+	let words = crate::utils::bytes_to_be_words(&data[.. 512].to_vec());
+
+	Some(Strings {
+		serial: id::read_string(&words, 4, 13),
+		firmware: id::read_string(&words, 16, 19),
+		model: id::read_string(&words, 24, 43),
+	})
+}
+
+/// Security page (0x06)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Security {
+	/// `None` if Security feature set is not supported
+	pub status: Option<SecurityStatus>,
+	pub trusted_computing_supported: Option<bool>,
+}
+
+pub fn parse_security(data: &[u8]) -> Option<Security> {
+	if !check_header(data, Page::Security) { return None; }
+
+	// unlike IDENTIFY DEVICE word 128, master password capability is in bit 6 rather than bit 8
+	let status = valid_qword(data, 16)
+		.filter(|q| q & 1 != 0)
+		.map(|q| SecurityStatus {
+			enabled: q & (1 << 1) != 0,
+			locked: q & (1 << 2) != 0,
+			frozen: q & (1 << 3) != 0,
+			count_expired: q & (1 << 4) != 0,
+			enhanced_erase_supported: q & (1 << 5) != 0,
+			level: if q & (1 << 6) != 0 { SecurityLevel::Maximum } else { SecurityLevel::High },
+			master_password_id: valid_qword(data, 8)
+				.map(|q| q as u16)
+				.filter(|&id| id != 0x0000 && id != 0xffff),
+			erase_time: valid_qword(data, 32).and_then(|q| id::erase_time(q as u16)),
+			enhanced_erase_time: valid_qword(data, 24).and_then(|q| id::erase_time(q as u16)),
+		});
+
+	Some(Security {
+		status: status,
+		trusted_computing_supported: valid_qword(data, 40).map(|q| q & 1 != 0),
+	})
+}
+
+/// Device Sleep timing, in milliseconds
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct DevSleepTiming {
+	/// DEVSLEEP_EXIT_TIMEOUT (DETO): maximum time device needs to become ready after DEVSLP signal is negated
+	pub exit_timeout: u8,
+	/// MINIMUM DEVSLP ASSERTION TIME (MDAT): how long host should keep DEVSLP signal asserted
+	pub min_assertion_time: u8,
+}
+
+/// Serial ATA page (0x08)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SerialATA {
+	/// SATA CAPABILITIES field, as is
+	pub capabilities: Option<u64>,
+	/// CURRENT SATA SETTINGS field, as is
+	pub current_settings: Option<u64>,
+	pub devsleep_timing: Option<DevSleepTiming>,
+}
+
+pub fn parse_sata(data: &[u8]) -> Option<SerialATA> {
+	if !check_header(data, Page::SerialATA) { return None; }
+
+	Some(SerialATA {
+		capabilities: valid_qword(data, 8),
+		current_settings: valid_qword(data, 16),
+		// DEVSLP TIMING VARIABLES; zero values mean device uses defaults from SATA specification (20 ms for DETO, 10 ms for MDAT)
+		devsleep_timing: valid_qword(data, 48).map(|q| DevSleepTiming {
+			exit_timeout: match (q >> 8) & 0xff {
+				0 => 20,
+				deto => deto as u8,
+			},
+			min_assertion_time: match q & 0x1f {
+				0 => 10,
+				mdat => mdat as u8,
+			},
+		}),
+	})
+}
+
+/// All the pages of the Identify Device Data log this crate knows how to parse; pages that device does not provide are `None`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct DeviceData {
+	/// supported pages, as reported by the device
+	pub pages: Vec<u8>,
+
+	pub capacity: Option<Capacity>,
+	pub capabilities: Option<SupportedCapabilities>,
+	pub settings: Option<CurrentSettings>,
+	pub strings: Option<Strings>,
+	pub security: Option<Security>,
+	pub sata: Option<SerialATA>,
+}
+
+impl DeviceData {
+	/// Overrides IDENTIFY DEVICE data in `id` with corresponding values from this log, where available.
+	pub fn update_id(&self, id: &mut id::Id) {
+		if let Some(capacity) = &self.capacity {
+			if let Some(size) = capacity.sector_size_log { id.sector_size_log = size; }
+			if let Some(size) = capacity.sector_size_phy { id.sector_size_phy = size; }
+			if let Some(sectors) = capacity.sectors { id.sectors = sectors; }
+			id.capacity = id.sectors * id.sector_size_log as u64;
+		}
+
+		if let Some(capabilities) = &self.capabilities {
+			if let Some(rpm) = capabilities.rpm { id.rpm = rpm; }
+			if capabilities.form_factor.is_some() { id.form_factor = capabilities.form_factor; }
+			if capabilities.zoned.is_some() { id.zoned = capabilities.zoned; }
+			if let Some(trim) = capabilities.trim_supported { id.trim_supported = trim; }
+			if capabilities.wwn.is_some() { id.wwn_supported = true; }
+		}
+
+		if let Some(strings) = &self.strings {
+			// some devices leave this page empty
+			if !strings.serial.is_empty() { id.serial = strings.serial.clone(); }
+			if !strings.firmware.is_empty() { id.firmware = strings.firmware.clone(); }
+			if !strings.model.is_empty() { id.model = strings.model.clone(); }
+		}
+
+		if let Some(security) = &self.security {
+			if security.status.is_some() { id.security_status = security.status.clone(); }
+			if let Some(tc) = security.trusted_computing_supported { id.trusted_computing_supported = tc; }
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+
+use super::id::read_string;
+use super::is_set;
+
+/// Command set used by the device, as in SPC peripheral device type
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum DeviceType {
+	DirectAccess,
+	SequentialAccess, // tape
+	CDROM, // CD/DVD/BD
+	OpticalMemory,
+	MediumChanger,
+	Unknown,
+	Other(u8),
+}
+
+impl fmt::Display for DeviceType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DeviceType::DirectAccess => write!(f, "direct-access device"),
+			DeviceType::SequentialAccess => write!(f, "sequential-access device (tape)"),
+			DeviceType::CDROM => write!(f, "CD/DVD device"),
+			DeviceType::OpticalMemory => write!(f, "optical memory device"),
+			DeviceType::MediumChanger => write!(f, "medium changer"),
+			DeviceType::Unknown => write!(f, "unknown device type"),
+			DeviceType::Other(t) => write!(f, "device type 0x{:02x}", t),
+		}
+	}
+}
+
+/// IDENTIFY PACKET DEVICE data structure (see ATA8-ACS, 7.17)
+#[derive(Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct PacketId {
+	pub is_atapi: bool, // probably redundant
+	pub incomplete: bool, // content of words other than 0 might be invalid
+
+	pub serial: String,
+	pub firmware: String,
+	pub model: String,
+
+	pub device_type: DeviceType,
+	/// length of the command packet, in bytes (12 or 16)
+	pub command_packet_size: Option<u8>,
+	pub removable: bool,
+
+	pub dma_supported: bool,
+	pub power_mgmt_supported: bool,
+	pub write_cache: bool,
+	pub read_look_ahead: bool,
+}
+
+pub fn parse_id_packet(data: &Vec<u8>) -> Option<PacketId> {
+	if data.len() < 512 { return None; }
+	let data = crate::utils::bytes_to_be_words(data);
+
+	Some(PacketId {
+		// w0 bits 15:14 are 10b for ATAPI devices
+		is_atapi: data[0] >> 14 == 0b10,
+		incomplete: is_set(data[0], 2),
+
+		serial: read_string(&data, 10, 19),
+		firmware: read_string(&data, 23, 26),
+		model: read_string(&data, 27, 46),
+
+		device_type: match (data[0] >> 8) & 0x1f {
+			0x00 => DeviceType::DirectAccess,
+			0x01 => DeviceType::SequentialAccess,
+			0x05 => DeviceType::CDROM,
+			0x07 => DeviceType::OpticalMemory,
+			0x08 => DeviceType::MediumChanger,
+			0x1f => DeviceType::Unknown,
+			t => DeviceType::Other(t as u8),
+		},
+		command_packet_size: match data[0] & 0b11 {
+			0b00 => Some(12),
+			0b01 => Some(16),
+			_ => None, // reserved
+		},
+		removable: is_set(data[0], 7),
+
+		dma_supported: is_set(data[49], 8),
+		// w82, w85 are only valid if w83, w87 are (bit 15 is 0 and bit 14 is 1)
+		power_mgmt_supported: data[83] & 0xc000 == 0x4000 && is_set(data[82], 3),
+		write_cache: data[87] & 0xc000 == 0x4000 && is_set(data[85], 5),
+		read_look_ahead: data[87] & 0xc000 == 0x4000 && is_set(data[85], 6),
+	})
+}
@@ -1,3 +1,17 @@
 pub mod attr;
+pub mod smart_data;
 pub mod id;
+pub mod id_packet;
+pub mod id_log;
+pub mod device_stats;
+pub mod pending_defects;
+pub mod ncq_error;
 pub mod health;
+pub mod power;
+pub mod dco;
+pub mod capacity;
+
+// for bit flags in IDENTIFY DEVICE words, SMART data bytes and such
+pub(crate) fn is_set<T: Into<u64>>(x: T, bit: usize) -> bool {
+	x.into() & (1 << bit) != 0
+}
@@ -0,0 +1,60 @@
+/*!
+NCQ Command Error log (log address 0x10), see ACS-4.
+
+Once a queued command fails, device aborts all the outstanding commands and refuses new ones until host reads this log (which it does with a non-queued READ LOG EXT), so normally it is the OS that gets to read it first. What's left here afterwards is the description of the last NCQ error.
+*/
+
+/// Log address to use with READ LOG EXT
+pub const LOG_ADDRESS: u8 = 0x10;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct NCQError {
+	/// tag of the failed queued command; `None` if the error was caused by a non-queued command (NQ bit)
+	pub tag: Option<u8>,
+	/// Status register of the failed command; ERR bit (bit 0) is not set if there was no error to report
+	pub status: u8,
+	/// Error register of the failed command
+	pub error: u8,
+	pub device: u8,
+	/// LBA of the first sector the failed command failed to read or write
+	pub lba: u64,
+	pub count: u16,
+
+	// these three are only meaningful if Sense Data Reporting feature set is enabled
+	pub sense_key: u8,
+	pub asc: u8,
+	pub ascq: u8,
+
+	/// whether all 512 bytes of the log add up to zero, as they should
+	pub checksum_valid: bool,
+}
+
+impl NCQError {
+	/// Returns whether the log describes an actual error.
+	pub fn is_error(&self) -> bool {
+		self.status & 1 != 0
+	}
+}
+
+pub fn parse_ncq_error(data: &[u8]) -> Option<NCQError> {
+	if data.len() < 512 { return None; }
+
+	let lba = [4, 5, 6, 8, 9, 10].iter().rev()
+		.fold(0u64, |lba, &i| (lba << 8) | data[i] as u64);
+
+	Some(NCQError {
+		tag: if data[0] & 0x80 != 0 { None } else { Some(data[0] & 0x1f) },
+		status: data[2],
+		error: data[3],
+		device: data[7],
+		lba: lba,
+		count: ((data[13] as u16) << 8) | data[12] as u16,
+
+		sense_key: data[256] & 0xf,
+		asc: data[257],
+		ascq: data[258],
+
+		checksum_valid: data[.. 512].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0,
+	})
+}
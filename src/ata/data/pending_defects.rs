@@ -0,0 +1,50 @@
+/*!
+Pending Defects log (log address 0x0c), see ACS-4.
+
+Lists logical sectors that the device failed to read and is going to reallocate (or to rewrite in place) on the next write; the number of these is what SMART attribute 197 (Current Pending Sector Count) usually reports.
+
+The log is a list of 16-byte descriptors that spans as many pages as needed; the first 16 bytes of page 0 are taken by the header, so page 0 holds 31 descriptors, and every subsequent page holds 32 of them.
+*/
+
+use byteorder::{ReadBytesExt, LittleEndian};
+
+/// Log address to use with READ LOG EXT
+pub const LOG_ADDRESS: u8 = 0x0c;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct PendingDefect {
+	pub lba: u64,
+	/// device power-on hours at the moment this LBA was added to the list; `None` if not reported
+	pub power_on_hours: Option<u32>,
+}
+
+/// Returns the number of descriptors in the log, as reported in the header of page 0.
+pub fn parse_count(data: &[u8]) -> Option<u32> {
+	if data.len() < 512 { return None; }
+	Some((&data[0 .. 4]).read_u32::<LittleEndian>().unwrap())
+}
+
+/// Returns how many pages of the log should be read to get `count` descriptors.
+pub fn pages(count: u32) -> u16 {
+	// header + descriptors, rounded up to a whole page
+	(16 + 16 * count as u64).div_ceil(512).min(0xffff) as u16
+}
+
+/**
+Parses descriptors from `data`, which is expected to be consecutive pages of the log starting from page 0.
+
+If `data` is shorter than the header says, only the descriptors that fit are returned.
+*/
+pub fn parse_pending_defects(data: &[u8]) -> Option<Vec<PendingDefect>> {
+	let count = parse_count(data)? as usize;
+
+	// descriptors simply continue from one page to the next, so there's no need to deal with page boundaries
+	Some(data[16 ..].chunks_exact(16).take(count).map(|desc| {
+		let hours = (&desc[0 .. 4]).read_u32::<LittleEndian>().unwrap();
+		PendingDefect {
+			lba: (&desc[8 .. 16]).read_u64::<LittleEndian>().unwrap(),
+			power_on_hours: if hours == 0xffff_ffff { None } else { Some(hours) },
+		}
+	}).collect())
+}
@@ -0,0 +1,87 @@
+use crate::ata;
+
+/**
+Device power mode, as reported by CHECK POWER MODE.
+
+There's no variant for PM3: Sleep, as sleeping devices do not process commands until reset; expect CHECK POWER MODE itself to fail instead.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum PowerMode {
+	/// PM2: Standby (Standby_z for devices that implement EPC feature set)
+	Standby,
+	/// PM2: Standby_y (EPC)
+	StandbyY,
+	/// PM1: Idle
+	Idle,
+	/// PM1: Idle_a (EPC)
+	IdleA,
+	/// PM1: Idle_b (EPC)
+	IdleB,
+	/// PM1: Idle_c (EPC)
+	IdleC,
+	/// PM0: Active, or PM1: Idle; device does not tell which one it is
+	ActiveOrIdle,
+	Unknown(u8),
+}
+
+impl PowerMode {
+	/// Whether device is in one of the Standby modes, and will probably spin up its platters to process most of the commands.
+	pub fn is_standby(&self) -> bool {
+		matches!(self, PowerMode::Standby | PowerMode::StandbyY)
+	}
+
+	/// Whether device is in one of the Idle modes (but not in `ActiveOrIdle`, as it is ambiguous).
+	pub fn is_idle(&self) -> bool {
+		matches!(self, PowerMode::Idle | PowerMode::IdleA | PowerMode::IdleB | PowerMode::IdleC)
+	}
+}
+
+// ACS-3, 7.3 CHECK POWER MODE
+pub fn parse_power_mode(reg: &ata::RegistersRead) -> PowerMode {
+	use self::PowerMode::*;
+	match reg.sector_count {
+		0x00 => Standby,
+		0x01 => StandbyY,
+		0x80 => Idle,
+		0x81 => IdleA,
+		0x82 => IdleB,
+		0x83 => IdleC,
+		0xff => ActiveOrIdle,
+		// 0x40, 0x41 are obsolete NV Cache power modes
+		x => Unknown(x),
+	}
+}
+
+/**
+Encodes Standby timer period (as used by IDLE and STANDBY commands) for a given number of `seconds`, rounding it up to the nearest value the encoding allows.
+
+`0` disables the timer. Returns `None` if `seconds` exceeds the maximum value (5.5 hours).
+*/
+pub fn encode_standby_timer(seconds: u32) -> Option<u8> {
+	match seconds {
+		0 => Some(0),
+		// 1..=240: timeout is value × 5 s
+		1 ..= 1200 => Some(seconds.div_ceil(5) as u8),
+		// 241..=251: timeout is (value − 240) × 30 min
+		1201 ..= 19800 => Some((240 + seconds.div_ceil(1800)) as u8),
+		_ => None,
+	}
+}
+
+/**
+Decodes Standby timer period into the number of seconds.
+
+Returns `None` for the reserved and vendor-specific values, as well as for disabled timer (`0`).
+*/
+pub fn decode_standby_timer(value: u8) -> Option<u32> {
+	match value {
+		0 => None,
+		1 ..= 240 => Some(value as u32 * 5),
+		241 ..= 251 => Some((value as u32 - 240) * 30 * 60),
+		252 => Some(21 * 60),
+		253 => None, // vendor-specific, somewhere between 8 and 12 hours
+		254 => None, // reserved
+		255 => Some(21 * 60 + 15),
+	}
+}
@@ -0,0 +1,232 @@
+/*!
+SMART READ DATA response (see ATA8-ACS, description of SMART READ DATA command).
+
+Besides the table of [attributes](../attr/index.html), this structure tells the state of off-line data collection and of the last self-test, what self-tests device supports, and how long are they expected to take.
+*/
+
+use std::fmt;
+
+use super::attr::{self, SmartAttribute};
+use crate::drivedb;
+use super::is_set;
+
+/// Off-line data collection status, bits 6:0 of byte 362
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum OfflineStatus {
+	NeverStarted,
+	Completed,
+	InProgress,
+	/// suspended by an interrupting command from host
+	Suspended,
+	/// aborted by an interrupting command from host
+	AbortedByHost,
+	/// aborted by the device with a fatal error
+	AbortedByDevice,
+	/// reserved or vendor-specific value
+	Other(u8),
+}
+
+impl fmt::Display for OfflineStatus {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::OfflineStatus::*;
+		match self {
+			NeverStarted => write!(f, "never started"),
+			Completed => write!(f, "completed without error"),
+			InProgress => write!(f, "in progress"),
+			Suspended => write!(f, "suspended by an interrupting command from host"),
+			AbortedByHost => write!(f, "aborted by an interrupting command from host"),
+			AbortedByDevice => write!(f, "aborted by the device with a fatal error"),
+			Other(x) => write!(f, "unknown (0x{:02x})", x),
+		}
+	}
+}
+
+/// Self-test execution status, bits 7:4 of byte 363
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum SelfTestStatus {
+	/// the last self-test completed without error, or no self-test has ever been run
+	Completed,
+	AbortedByHost,
+	/// interrupted by host with a hardware or software reset
+	Interrupted,
+	/// fatal error or unknown test error occurred, device was unable to complete the self-test
+	FatalError,
+	/// completed with an unknown element failing
+	FailedUnknown,
+	/// completed with the electrical element failing
+	FailedElectrical,
+	/// completed with the servo (and/or seek) element failing
+	FailedServo,
+	/// completed with the read element failing
+	FailedRead,
+	/// completed with failure because of suspected handling damage
+	FailedHandlingDamage,
+	InProgress,
+	Reserved(u8),
+}
+
+impl SelfTestStatus {
+	/// Returns whether the last self-test found anything wrong with the device.
+	pub fn is_failure(&self) -> bool {
+		use self::SelfTestStatus::*;
+		matches!(self, FatalError | FailedUnknown | FailedElectrical | FailedServo | FailedRead | FailedHandlingDamage)
+	}
+}
+
+impl fmt::Display for SelfTestStatus {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::SelfTestStatus::*;
+		match self {
+			Completed => write!(f, "completed without error (or never run)"),
+			AbortedByHost => write!(f, "aborted by host"),
+			Interrupted => write!(f, "interrupted by host with a reset"),
+			FatalError => write!(f, "fatal or unknown error, test was not completed"),
+			FailedUnknown => write!(f, "completed, unknown element failed"),
+			FailedElectrical => write!(f, "completed, electrical element failed"),
+			FailedServo => write!(f, "completed, servo/seek element failed"),
+			FailedRead => write!(f, "completed, read element failed"),
+			FailedHandlingDamage => write!(f, "completed, suspected handling damage"),
+			InProgress => write!(f, "in progress"),
+			Reserved(x) => write!(f, "unknown (0x{:x})", x),
+		}
+	}
+}
+
+/// Off-line data collection capabilities (byte 367) and SMART capabilities (bytes 368–369)
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Capabilities {
+	/// SMART EXECUTE OFF-LINE IMMEDIATE is supported
+	pub offline_immediate: bool,
+	/// automatic off-line data collection can be enabled or disabled (this one is vendor-specific, but widely implemented)
+	pub auto_offline: bool,
+	/// if set, off-line data collection is aborted by any new command; otherwise, it is suspended and resumed later
+	pub offline_aborted_by_command: bool,
+	pub offline_read_scanning: bool,
+	/// short and extended self-tests are supported
+	pub self_test: bool,
+	pub conveyance_self_test: bool,
+	pub selective_self_test: bool,
+
+	/// device saves SMART data before entering power-saving mode
+	pub saves_before_power_saving: bool,
+	/// SMART ENABLE/DISABLE ATTRIBUTE AUTOSAVE is supported
+	pub attribute_autosave: bool,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct SmartData {
+	pub revision: u16,
+
+	pub offline_status: OfflineStatus,
+	pub offline_auto_enabled: bool,
+	/// total time to complete off-line data collection, in seconds
+	pub offline_time: u16,
+
+	pub self_test_status: SelfTestStatus,
+	/// percentage of the self-test remaining, in 10% steps; only meaningful if self-test is in progress
+	pub self_test_remaining: u8,
+
+	pub capabilities: Capabilities,
+	pub error_logging_supported: bool,
+
+	// recommended polling times, in minutes; `None` if corresponding self-test is not supported
+	pub short_self_test_time: Option<u16>,
+	pub extended_self_test_time: Option<u16>,
+	pub conveyance_self_test_time: Option<u16>,
+
+	/// whether all 512 bytes of the structure add up to zero, as they should
+	pub checksum_valid: bool,
+
+	pub attributes: Vec<SmartAttribute>,
+}
+
+/// Parses SMART READ DATA response, along with the attributes (see [`attr::parse_smart_values()`](../attr/fn.parse_smart_values.html) for the meaning of `raw_thresh` and `meta`). Firmware bugs from `meta` are taken into account.
+pub fn parse_smart_data(data: &Vec<u8>, raw_thresh: &Vec<u8>, meta: &Option<drivedb::DriveMeta>) -> Option<SmartData> {
+	let attributes = attr::parse_smart_values(data, raw_thresh, meta)?;
+
+	let offline_status = {
+		use self::OfflineStatus::*;
+		match data[362] & 0x7f {
+			0x00 => NeverStarted,
+			0x02 => Completed,
+			0x03 => InProgress,
+			0x04 => Suspended,
+			0x05 => AbortedByHost,
+			0x06 => AbortedByDevice,
+			x => Other(x),
+		}
+	};
+
+	// some Samsung drives keep reporting the last self-test as in progress, with 0% remaining, after it has completed
+	let self_test_byte = match data[363] {
+		0xf0 if meta.as_ref().map(|meta| meta.has_firmware_bug(drivedb::FirmwareBug::Samsung3)).unwrap_or(false) => 0x00,
+		x => x,
+	};
+
+	let self_test_status = {
+		use self::SelfTestStatus::*;
+		match self_test_byte >> 4 {
+			0 => Completed,
+			1 => AbortedByHost,
+			2 => Interrupted,
+			3 => FatalError,
+			4 => FailedUnknown,
+			5 => FailedElectrical,
+			6 => FailedServo,
+			7 => FailedRead,
+			8 => FailedHandlingDamage,
+			15 => InProgress,
+			x => Reserved(x),
+		}
+	};
+
+	let capabilities = Capabilities {
+		offline_immediate: is_set(data[367], 0),
+		auto_offline: is_set(data[367], 1),
+		offline_aborted_by_command: is_set(data[367], 2),
+		offline_read_scanning: is_set(data[367], 3),
+		self_test: is_set(data[367], 4),
+		conveyance_self_test: is_set(data[367], 5),
+		selective_self_test: is_set(data[367], 6),
+
+		saves_before_power_saving: is_set(data[368], 0),
+		attribute_autosave: is_set(data[368], 1),
+	};
+
+	Some(SmartData {
+		revision: u16::from_le_bytes([data[0], data[1]]),
+
+		offline_status: offline_status,
+		offline_auto_enabled: is_set(data[362], 7),
+		offline_time: u16::from_le_bytes([data[364], data[365]]),
+
+		self_test_status: self_test_status,
+		self_test_remaining: (self_test_byte & 0xf) * 10,
+
+		error_logging_supported: is_set(data[370], 0),
+
+		short_self_test_time: if capabilities.self_test {
+			Some(data[372] as u16)
+		} else { None },
+		// 0xff means the time does not fit into a byte, and is in bytes 375–376 instead
+		extended_self_test_time: if capabilities.self_test {
+			Some(match data[373] {
+				0xff => u16::from_le_bytes([data[375], data[376]]),
+				time => time as u16,
+			})
+		} else { None },
+		conveyance_self_test_time: if capabilities.conveyance_self_test {
+			Some(data[374] as u16)
+		} else { None },
+
+		capabilities: capabilities,
+
+		checksum_valid: data[.. 512].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0,
+
+		attributes: attributes,
+	})
+}
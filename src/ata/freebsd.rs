@@ -16,37 +16,55 @@ impl ATADevice<Device> {
 
 		let mut data: [u8; 512] = [0; 512];
 
+		let flags = {
+			use self::Direction::*;
+			match dir {
+				From => ccb_flags_CAM_DIR_IN,
+				To => unimplemented!(), // use ata_platform_do_out() instead
+				Both => unimplemented!(), //CAM_DIR_BOTH,
+				None => ccb_flags_CAM_DIR_NONE,
+			}
+		};
+
+		let regs = self.ataio(flags, regs, &mut data, timeout)?;
+		Ok((regs, data.to_vec()))
+	}
+
+	fn ata_platform_do_out(&self, regs: &ata::RegistersWrite, data: &[u8], timeout: u32) -> Result<ata::RegistersRead, io::Error> {
+		// CAM only reads from the buffer with CAM_DIR_OUT, yet it wants a mutable pointer
+		let mut data = data.to_vec();
+
+		self.ataio(ccb_flags_CAM_DIR_OUT, regs, &mut data, timeout)
+	}
+
+	// common part of `ata_platform_do()` and `ata_platform_do_out()`
+	fn ataio(&self, flags: ccb_flags, regs: &ata::RegistersWrite, data: &mut [u8], timeout: u32) -> Result<ata::RegistersRead, io::Error> {
 		let ccb = CCB::new(&self.device.dev);
 
 		unsafe {
 			let h = ccb.ccb_h();
 			h.func_code = xpt_opcode_XPT_ATA_IO;
-			h.flags = {
-				use self::Direction::*;
-				match dir {
-					From => ccb_flags_CAM_DIR_IN,
-					To => unimplemented!(), //CAM_DIR_OUT,
-					Both => unimplemented!(), //CAM_DIR_BOTH,
-					None => ccb_flags_CAM_DIR_NONE,
-				}
-			};
+			h.flags = flags;
 			h.retry_count = 0;
-			h.timeout = timeout * 1000;
+			h.timeout = timeout.saturating_mul(1000);
 
 			let ataio = ccb.ataio();
 			ataio.data_ptr = data.as_mut_ptr();
-			ataio.dxfer_len = 512;
+			ataio.dxfer_len = data.len() as u32;
 			ataio.ata_flags = 0;
 
+			let hob = regs.hob.unwrap_or_default();
+
 			ataio.cmd.command	= regs.command;
 			ataio.cmd.features	= regs.features;
-			ataio.cmd.lba_low_exp	= 0;
+			ataio.cmd.lba_low_exp	= hob.sector;
 			ataio.cmd.lba_low	= regs.sector;
-			ataio.cmd.lba_mid_exp	= 0;
+			ataio.cmd.lba_mid_exp	= hob.cyl_low;
 			ataio.cmd.lba_mid	= regs.cyl_low;
-			ataio.cmd.lba_high_exp	= 0;
+			ataio.cmd.lba_high_exp	= hob.cyl_high;
 			ataio.cmd.lba_high	= regs.cyl_high;
 			ataio.cmd.device	= regs.device;
+			ataio.cmd.sector_count_exp	= hob.sector_count;
 			ataio.cmd.sector_count	= regs.sector_count;
 
 			ataio.cmd.flags = (CAM_ATAIO_NEEDRESULT | CAM_ATAIO_48BIT) as u8;
@@ -62,7 +80,7 @@ impl ATADevice<Device> {
 
 		let ataio = unsafe { ccb.ataio() };
 
-		Ok((ata::RegistersRead {
+		Ok(ata::RegistersRead {
 			error: ataio.res.error,
 
 			sector_count: ataio.res.sector_count,
@@ -73,6 +91,13 @@ impl ATADevice<Device> {
 			device: ataio.res.device,
 
 			status: ataio.res.status,
-		}, data.to_vec()))
+
+			hob: regs.hob.map(|_| ata::RegistersHOB {
+				sector_count: ataio.res.sector_count_exp,
+				sector: ataio.res.lba_low_exp,
+				cyl_low: ataio.res.lba_mid_exp,
+				cyl_high: ataio.res.lba_high_exp,
+			}),
+		})
 	}
 }
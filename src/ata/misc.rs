@@ -39,10 +39,11 @@ use crate::Direction;
 #[cfg(not(target_os = "linux"))]
 use Device;
 
-use crate::ata::{ATADevice, RegistersRead, RegistersWrite, Command, SMARTFeature};
+use crate::ata::{ATADevice, RegistersRead, RegistersWrite, RegistersHOB, Command, SMARTFeature, SetFeature, SATAFeature, DCOFeature, AMAFeature};
 use crate::scsi::{self, SCSIDevice};
+use crate::scsi::data::sense::key::{SenseKey, decode_asc};
 
-use crate::ata::data::{id, health, attr};
+use crate::ata::data::{id, id_packet, id_log, device_stats, pending_defects, ncq_error, health, attr, smart_data, power, dco, capacity};
 use crate::drivedb;
 
 use std::io;
@@ -62,30 +63,145 @@ quick_error! {
 		ShortData(context: &'static str, expected: usize, got: usize) {
 			display("Short {} data: expected {} bytes, got {}", context, expected, got)
 		}
+		/// Device set ERR bit in the Status register; Error register usually explains why (e.g. bit 2, ABRT, for unsupported commands or arguments)
+		Failed(status: u8, error: u8) {
+			display("ATA command failed: status=0x{:02x}, error=0x{:02x}", status, error)
+		}
+		/// Same as `Failed`, but device also provided sense data explaining the failure (see Sense Data Reporting feature set)
+		Sense(status: u8, error: u8, key: SenseKey, asc: u8, ascq: u8) {
+			display("ATA command failed: status=0x{:02x}, error=0x{:02x}: {:?} ({})",
+				status, error,
+				key,
+				decode_asc(*asc, *ascq)
+					.map(|x| x.to_string())
+					.unwrap_or_else(|| format!("unknown additional sense code: {:02x} {:02x}", asc, ascq)))
+		}
+	}
+}
+
+// for non-data commands, this is the only way to find out whether the device accepted the command
+pub(crate) fn check_status(regs: &RegistersRead) -> Result<(), Error> {
+	if regs.status & 1 != 0 {
+		Err(Error::Failed(regs.status, regs.error))
+	} else {
+		Ok(())
 	}
 }
 
+/*
+If the command failed and the device says it has sense data for us (SENSE DATA AVAILABLE, bit 1 of the Status register), fetch it, as it explains the failure much better than the Error register does.
+
+Bit 1 used to mean something else in older standards, so IDENTIFY DEVICE goes first to make sure Sense Data Reporting is actually enabled. Failure to fetch sense data is not an error in itself.
+*/
+fn fetch_sense<T: Misc + ?Sized>(dev: &T, command: u8, regs: &RegistersRead) -> Result<(), Error> {
+	if command == Command::RequestSenseDataExt as u8 || command == Command::Identify as u8 || regs.status & 0b11 != 0b11 {
+		return Ok(());
+	}
+
+	let reporting = identify(dev).ok()
+		.and_then(|data| id::parse_id(&data))
+		.map(|id| id.sense_data_reporting);
+	if reporting != Some(id::Ternary::Enabled) {
+		debug!("status says sense data is available, but Sense Data Reporting is {:?}", reporting);
+		return Ok(());
+	}
+
+	match dev.request_sense_data() {
+		Ok(Some((key, asc, ascq))) => Err(Error::Sense(regs.status, regs.error, key, asc, ascq)),
+		Ok(None) => Ok(()),
+		Err(e) => {
+			debug!("cannot fetch sense data: {}", e);
+			Ok(())
+		},
+	}
+}
+
+// issues IDENTIFY DEVICE and returns raw data
+fn identify<T: Misc + ?Sized>(dev: &T) -> Result<Vec<u8>, Error> {
+	let (regs, data) = dev.ata_do(Direction::From, &RegistersWrite {
+		command: Command::Identify as u8,
+		sector: 1,
+		features: 0,
+		sector_count: 1,
+		cyl_high: 0,
+		cyl_low: 0,
+		device: 0,
+		hob: None,
+	})?;
+	// ATAPI devices abort this command
+	check_status(&regs)?;
+	Ok(data)
+}
+
+// common part of IDLE/STANDBY commands
+fn power_cmd<T: Misc + ?Sized>(dev: &T, command: Command, timer: u8) -> Result<(), Error> {
+	let (regs, _) = dev.ata_do(Direction::None, &RegistersWrite {
+		command: command as u8,
+		features: 0,
+		sector_count: timer,
+		sector: 0,
+		cyl_low: 0,
+		cyl_high: 0,
+		device: 0,
+		hob: None,
+	})?;
+	check_status(&regs)
+}
+
 /// See [module documentation](index.html).
 pub trait Misc {
 	// This one not only invokes ata_do() from `ATADevice<Whatever>`, but also converts into our own error type.
 	fn ata_do(&self, dir: Direction, regs: &RegistersWrite) -> Result<(RegistersRead, Vec<u8>), Error>;
+	// Same, for ata_do_out().
+	fn ata_do_out(&self, regs: &RegistersWrite, data: &[u8], timeout: u32) -> Result<RegistersRead, Error>;
 
 	/// Issues IDENTIFY DEVICE command, returning a wide range of data, from model name to status of various features.
 	fn get_device_id(&self) -> Result<id::Id, Error> {
 		info!("reading device identification packet");
 
-		let (_, data) = self.ata_do(Direction::From, &RegistersWrite {
-			command: Command::Identify as u8,
-			sector: 1,
+		let data = identify(self)?;
+		id::parse_id(&data)
+			.ok_or(Error::ShortData("IDENTIFY DEVICE", 512, data.len()))
+	}
+
+	/// Same as [`get_device_id()`](#method.get_device_id), but also applies workarounds for firmware bugs that drivedb entry `meta` lists (see `drivedb::FirmwareBug`).
+	fn get_device_id_fixed(&self, meta: &Option<drivedb::DriveMeta>) -> Result<id::Id, Error> {
+		let swap_id = meta.as_ref()
+			.map(|meta| meta.has_firmware_bug(drivedb::FirmwareBug::SwapId))
+			.unwrap_or(false);
+		if !swap_id {
+			return self.get_device_id();
+		}
+
+		info!("reading device identification packet, with strings byte-swapped");
+
+		let mut data = identify(self)?;
+		id::swap_strings(&mut data);
+		let mut id = id::parse_id(&data)
+			.ok_or(Error::ShortData("IDENTIFY DEVICE", 512, data.len()))?;
+		// swapping broke it, and the device is not to blame
+		id.checksum_valid = None;
+		Ok(id)
+	}
+
+	/// Issues IDENTIFY PACKET DEVICE command. Only ATAPI devices (optical, tape drives etc.) support this; use `get_device_id()` for the rest.
+	fn get_packet_device_id(&self) -> Result<id_packet::PacketId, Error> {
+		info!("reading packet device identification");
+
+		let (regs, data) = self.ata_do(Direction::From, &RegistersWrite {
+			command: Command::IdentifyPacket as u8,
+			sector: 0,
 			features: 0,
-			sector_count: 1,
+			sector_count: 0,
 			cyl_high: 0,
 			cyl_low: 0,
 			device: 0,
+			hob: None,
 		})?;
+		check_status(&regs)?;
 
-		id::parse_id(&data)
-			.ok_or(Error::ShortData("IDENTIFY DEVICE", 512, data.len()))
+		id_packet::parse_id_packet(&data)
+			.ok_or(Error::ShortData("IDENTIFY PACKET DEVICE", 512, data.len()))
 	}
 
 	/// Issues SMART RETURN STATUS command, returns `Some(false)` if device can no longer be considered reliable.
@@ -100,13 +216,312 @@ pub trait Misc {
 			cyl_low: 0x4f,
 			cyl_high: 0xc2,
 			device: 0,
+			hob: None,
 		})?;
 		Ok(health::parse_smart_status(&regs))
 	}
 
+	/// Issues CHECK POWER MODE command. Unlike most of the commands, this one does not spin up the device that is in Standby mode.
+	fn check_power_mode(&self) -> Result<power::PowerMode, Error> {
+		info!("checking power mode");
+
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: Command::CheckPowerMode as u8,
+			features: 0,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		})?;
+		check_status(&regs)?;
+		Ok(power::parse_power_mode(&regs))
+	}
+
+	/// Issues STANDBY IMMEDIATE command, causing device to spin down and enter Standby mode.
+	fn standby_immediate(&self) -> Result<(), Error> {
+		info!("entering standby mode");
+		power_cmd(self, Command::StandbyImmediate, 0)
+	}
+
+	/// Issues IDLE IMMEDIATE command, causing device to enter Idle mode.
+	fn idle_immediate(&self) -> Result<(), Error> {
+		info!("entering idle mode");
+		power_cmd(self, Command::IdleImmediate, 0)
+	}
+
+	/// Issues STANDBY command, causing device to enter Standby mode and setting Standby timer to `timer` (see [`power::encode_standby_timer()`](../data/power/fn.encode_standby_timer.html)).
+	fn standby(&self, timer: u8) -> Result<(), Error> {
+		info!("entering standby mode, timer={}", timer);
+		power_cmd(self, Command::Standby, timer)
+	}
+
+	/// Issues IDLE command, causing device to enter Idle mode and setting Standby timer to `timer` (see [`power::encode_standby_timer()`](../data/power/fn.encode_standby_timer.html)).
+	fn idle(&self, timer: u8) -> Result<(), Error> {
+		info!("entering idle mode, timer={}", timer);
+		power_cmd(self, Command::Idle, timer)
+	}
+
+	/// Issues READ NATIVE MAX ADDRESS command (or READ NATIVE MAX ADDRESS EXT if `ext` is set), returning the highest native LBA (which is not clipped by HPA).
+	fn read_native_max_address(&self, ext: bool) -> Result<u64, Error> {
+		info!("reading native max address, ext={}", ext);
+
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: if ext { Command::ReadNativeMaxAddressExt } else { Command::ReadNativeMaxAddress } as u8,
+			features: 0,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 1<<6, // LBA
+			hob: if ext { Some(RegistersHOB::default()) } else { None },
+		})?;
+		check_status(&regs)?;
+		Ok(regs.lba())
+	}
+
+	/// Issues GET NATIVE MAX ADDRESS EXT command (Accessible Max Address Configuration feature set), returning the highest native LBA.
+	fn get_native_max_address(&self) -> Result<u64, Error> {
+		info!("getting native max address");
+
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: Command::AccessibleMaxAddressConfiguration as u8,
+			features: AMAFeature::GetNativeMaxAddress as u8,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 1<<6, // LBA
+			hob: Some(RegistersHOB::default()),
+		})?;
+		check_status(&regs)?;
+		Ok(regs.lba())
+	}
+
+	/// Issues DEVICE CONFIGURATION IDENTIFY command, returning capabilities of the device that might be hidden by the Device Configuration Overlay.
+	fn device_configuration_identify(&self) -> Result<dco::DeviceConfiguration, Error> {
+		info!("reading device configuration overlay");
+
+		let (regs, data) = self.ata_do(Direction::From, &RegistersWrite {
+			command: Command::DeviceConfiguration as u8,
+			features: DCOFeature::Identify as u8,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		})?;
+		check_status(&regs)?;
+
+		dco::parse_dco(&data)
+			.ok_or(Error::ShortData("DEVICE CONFIGURATION IDENTIFY", 512, data.len()))
+	}
+
+	/// Issues READ LOG EXT command (General Purpose Logging feature set), returning one `page` of the log at `address`.
+	fn read_log_ext(&self, address: u8, page: u16) -> Result<Vec<u8>, Error> {
+		info!("reading log 0x{:02x}, page {}", address, page);
+
+		let (regs, data) = self.ata_do(Direction::From, &RegistersWrite {
+			command: Command::ReadLogExt as u8,
+			features: 0,
+			sector_count: 1, // only one page at a time
+			sector: address,
+			cyl_low: (page & 0xff) as u8,
+			cyl_high: 0,
+			device: 0,
+			hob: Some(RegistersHOB {
+				sector_count: 0,
+				sector: 0,
+				cyl_low: (page >> 8) as u8,
+				cyl_high: 0,
+			}),
+		})?;
+		check_status(&regs)?;
+
+		if data.len() < 512 {
+			return Err(Error::ShortData("READ LOG EXT", 512, data.len()));
+		}
+		Ok(data)
+	}
+
+	/**
+	Reads all the pages of the Identify Device Data log that this crate knows about.
+
+	Requires General Purpose Logging feature set (see `Id::gp_logging_supported`). Pages that are listed as supported but cannot be read are skipped.
+	*/
+	fn get_device_data_log(&self) -> Result<id_log::DeviceData, Error> {
+		use self::id_log::Page;
+
+		let list = self.read_log_ext(id_log::LOG_ADDRESS, Page::List as u16)?;
+		let pages = id_log::parse_list(&list)
+			.ok_or(Error::ShortData("Identify Device Data log", 512, list.len()))?;
+
+		// only read pages that are listed as supported
+		let read = |page: Page| if pages.contains(&(page as u8)) {
+			self.read_log_ext(id_log::LOG_ADDRESS, page as u16)
+				.map_err(|e| debug!("cannot read Identify Device Data log page {:?}: {}", page, e))
+				.ok()
+		} else { None };
+
+		Ok(id_log::DeviceData {
+			capacity: read(Page::Capacity).and_then(|data| id_log::parse_capacity(&data)),
+			capabilities: read(Page::SupportedCapabilities).and_then(|data| id_log::parse_supported_capabilities(&data)),
+			settings: read(Page::CurrentSettings).and_then(|data| id_log::parse_current_settings(&data)),
+			strings: read(Page::Strings).and_then(|data| id_log::parse_strings(&data)),
+			security: read(Page::Security).and_then(|data| id_log::parse_security(&data)),
+			sata: read(Page::SerialATA).and_then(|data| id_log::parse_sata(&data)),
+			pages: pages,
+		})
+	}
+
+	/**
+	Reads the pages of the Device Statistics log that this crate knows about.
+
+	Requires General Purpose Logging feature set (see `Id::gp_logging_supported`). Pages that are listed as supported but cannot be read are skipped.
+	*/
+	fn get_device_statistics(&self) -> Result<device_stats::DeviceStatistics, Error> {
+		use self::device_stats::Page;
+
+		let list = self.read_log_ext(device_stats::LOG_ADDRESS, Page::List as u16)?;
+		let pages = device_stats::parse_list(&list)
+			.ok_or(Error::ShortData("Device Statistics log", 512, list.len()))?;
+
+		// only read pages that are listed as supported
+		let read = |page: Page| if pages.contains(&(page as u8)) {
+			self.read_log_ext(device_stats::LOG_ADDRESS, page as u16)
+				.map_err(|e| debug!("cannot read Device Statistics log page {:?}: {}", page, e))
+				.ok()
+		} else { None };
+
+		Ok(device_stats::DeviceStatistics {
+			general: read(Page::General).and_then(|data| device_stats::parse_general(&data)),
+			rotating_media: read(Page::RotatingMedia).and_then(|data| device_stats::parse_rotating_media(&data)),
+			temperature: read(Page::Temperature).and_then(|data| device_stats::parse_temperature(&data)),
+			solid_state: read(Page::SolidState).and_then(|data| device_stats::parse_solid_state(&data)),
+			pages,
+		})
+	}
+
+	/**
+	Reads the Pending Defects log, i.e. the list of LBAs that device failed to read and is going to reallocate.
+
+	Requires General Purpose Logging feature set (see `Id::gp_logging_supported`); devices that do not implement this log (most of them, as it only appeared in ACS-4) abort the command.
+	*/
+	fn get_pending_defects(&self) -> Result<Vec<pending_defects::PendingDefect>, Error> {
+		let mut data = self.read_log_ext(pending_defects::LOG_ADDRESS, 0)?;
+		let count = pending_defects::parse_count(&data)
+			.ok_or(Error::ShortData("Pending Defects log", 512, data.len()))?;
+
+		for page in 1 .. pending_defects::pages(count) {
+			data.extend(self.read_log_ext(pending_defects::LOG_ADDRESS, page)?);
+		}
+
+		pending_defects::parse_pending_defects(&data)
+			.ok_or(Error::ShortData("Pending Defects log", 512, data.len()))
+	}
+
+	/**
+	Reads the NCQ Command Error log, which describes the last failed queued command.
+
+	Requires General Purpose Logging feature set and NCQ support (see `SataCapabilities::ncq`).
+	*/
+	fn get_ncq_error(&self) -> Result<ncq_error::NCQError, Error> {
+		let data = self.read_log_ext(ncq_error::LOG_ADDRESS, 0)?;
+		ncq_error::parse_ncq_error(&data)
+			.ok_or(Error::ShortData("NCQ Command Error log", 512, data.len()))
+	}
+
+	/**
+	Issues REQUEST SENSE DATA EXT command, returning sense key, ASC and ASCQ describing the last failed command, or `None` if there's no sense data to report.
+
+	Requires Sense Data Reporting feature set (see `Id::sense_data_reporting`). There's usually no need to call this directly: failed commands fetch sense data automatically, see `Error::Sense`.
+	*/
+	fn request_sense_data(&self) -> Result<Option<(SenseKey, u8, u8)>, Error> {
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: Command::RequestSenseDataExt as u8,
+			features: 0,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: Some(RegistersHOB::default()),
+		})?;
+		check_status(&regs)?;
+
+		// sense key is in LBA bits 19:16, ASC in 15:8, ASCQ in 7:0
+		let (key, asc, ascq) = (regs.cyl_high & 0xf, regs.cyl_low, regs.sector);
+		if key == 0 && asc == 0 && ascq == 0 {
+			Ok(None)
+		} else {
+			Ok(Some((SenseKey::from(key), asc, ascq)))
+		}
+	}
+
+	/**
+	Issues SECURITY FREEZE LOCK command, preventing any changes to Security feature set settings (passwords, erasure, etc.) until the next power cycle.
+
+	It is a good idea to issue this command on systems that do not need to manage passwords, as most BIOSes do, so that no one could lock the device with a random password.
+	*/
+	fn security_freeze_lock(&self) -> Result<(), Error> {
+		info!("freezing security settings");
+
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: Command::SecurityFreezeLock as u8,
+			features: 0,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		})?;
+		check_status(&regs)
+	}
+
+	/**
+	Compares accessible capacity from IDENTIFY DEVICE (`id`) against native capacity and the capacity that is reported by DEVICE CONFIGURATION IDENTIFY.
+
+	Commands that are not supported according to `id`, or fail, are skipped, leaving corresponding fields of `Capacity` set to `None`.
+	*/
+	fn get_capacity(&self, id: &id::Id) -> capacity::Capacity {
+		let native = if id.ama_supported {
+			Some(self.get_native_max_address())
+		} else if id.hpa != id::Ternary::Unsupported {
+			Some(self.read_native_max_address(id.lba48_supported))
+		} else { None };
+		let native = native
+			.and_then(|native| native
+				.map_err(|e| debug!("cannot get native max address: {}", e))
+				.ok()
+			)
+			// max LBA → number of sectors
+			.map(|lba| lba + 1);
+
+		let dco = if id.dco_supported {
+			self.device_configuration_identify()
+				.map_err(|e| debug!("cannot read DCO: {}", e))
+				.ok()
+				.map(|dco| dco.max_lba + 1)
+		} else { None };
+
+		capacity::Capacity {
+			accessible: id.sectors,
+			native: native,
+			dco: dco,
+		}
+	}
+
 	/// Issues SMART READ DATA and SMART READ THRESHOLDS commands, then renders their answers using optional [drivedb](../../drivedb/index.html) entry.
 	fn get_smart_attributes(&self, meta: &Option<drivedb::DriveMeta>) -> Result<Vec<attr::SmartAttribute>, Error> {
-		info!("reading SMART attributes and thresholds");
+		self.get_smart_data(meta).map(|data| data.attributes)
+	}
+
+	/// Same as [`get_smart_attributes()`](#method.get_smart_attributes), but also returns the rest of SMART READ DATA: off-line data collection and self-test status, SMART capabilities and self-test polling times.
+	fn get_smart_data(&self, meta: &Option<drivedb::DriveMeta>) -> Result<smart_data::SmartData, Error> {
+		info!("reading SMART data and thresholds");
 
 		let (_, data) = self.ata_do(Direction::From, &RegistersWrite {
 			command: Command::SMART as u8,
@@ -116,6 +531,7 @@ pub trait Misc {
 			cyl_low: 0x4f,
 			cyl_high: 0xc2,
 			device: 0,
+			hob: None,
 		})?;
 		let (_, thresh) = self.ata_do(Direction::From, &RegistersWrite {
 			command: Command::SMART as u8,
@@ -125,21 +541,113 @@ pub trait Misc {
 			cyl_low: 0x4f,
 			cyl_high: 0xc2,
 			device: 0,
+			hob: None,
 		})?;
 
-		attr::parse_smart_values(&data, &thresh, &meta)
+		smart_data::parse_smart_data(&data, &thresh, &meta)
 			.ok_or(Error::ShortData("SMART values", 512, data.len()))
 	}
+
+	/// Issues SET FEATURES command with the `feature` subcommand; `count` is an argument that some of the subcommands expect, and is ignored by the others.
+	fn set_feature(&self, feature: SetFeature, count: u8) -> Result<(), Error> {
+		info!("setting feature {:?}, count={}", feature, count);
+
+		let (regs, _) = self.ata_do(Direction::None, &RegistersWrite {
+			command: Command::SetFeatures as u8,
+			features: feature as u8,
+			sector_count: count,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		})?;
+		check_status(&regs)
+	}
+
+	/// Enables or disables volatile write cache.
+	fn set_write_cache(&self, enable: bool) -> Result<(), Error> {
+		self.set_feature(if enable { SetFeature::EnableWriteCache } else { SetFeature::DisableWriteCache }, 0)
+	}
+
+	/// Enables or disables read look-ahead.
+	fn set_read_look_ahead(&self, enable: bool) -> Result<(), Error> {
+		self.set_feature(if enable { SetFeature::EnableReadLookAhead } else { SetFeature::DisableReadLookAhead }, 0)
+	}
+
+	/// Enables or disables Sense Data Reporting feature set, i.e. whether failed commands provide sense data that is retrieved with REQUEST SENSE DATA EXT.
+	fn set_sense_data_reporting(&self, enable: bool) -> Result<(), Error> {
+		self.set_feature(SetFeature::SenseDataReporting, if enable { 1 } else { 0 })
+	}
+
+	/// Enables or disables Serial ATA `feature`.
+	fn set_sata_feature(&self, feature: SATAFeature, enable: bool) -> Result<(), Error> {
+		self.set_feature(if enable { SetFeature::EnableSATAFeature } else { SetFeature::DisableSATAFeature }, feature as u8)
+	}
+
+	/// Enables or disables Device-Initiated Interface Power Management, i.e. device's ability to put SATA link into Partial or Slumber state.
+	fn set_dipm(&self, enable: bool) -> Result<(), Error> {
+		self.set_sata_feature(SATAFeature::DIPM, enable)
+	}
+
+	/// Enables or disables Device Sleep, the lowest-power SATA link state that is entered by the host asserting DEVSLP signal.
+	fn set_devsleep(&self, enable: bool) -> Result<(), Error> {
+		self.set_sata_feature(SATAFeature::DevSleep, enable)
+	}
+
+	/**
+	Sets Advanced Power Management `level`, or disables APM if `level` is `None`.
+
+	Valid levels are:
+
+	- `0x01`: minimum power consumption with standby,
+	- `0x02..=0x7f`: intermediate power management levels with standby,
+	- `0x80`: minimum power consumption without standby,
+	- `0x81..=0xfd`: intermediate power management levels without standby,
+	- `0xfe`: maximum performance.
+	*/
+	fn set_apm(&self, level: Option<u8>) -> Result<(), Error> {
+		match level {
+			Some(level) => self.set_feature(SetFeature::EnableAPM, level),
+			None => self.set_feature(SetFeature::DisableAPM, 0),
+		}
+	}
+
+	/**
+	Sets Automatic Acoustic Management `level`, or disables AAM if `level` is `None`.
+
+	Valid levels are `0x80` (quietest) to `0xfe` (maximum performance); values below `0x80` are retired or vendor-specific.
+	*/
+	fn set_aam(&self, level: Option<u8>) -> Result<(), Error> {
+		match level {
+			Some(level) => self.set_feature(SetFeature::EnableAAM, level),
+			None => self.set_feature(SetFeature::DisableAAM, 0),
+		}
+	}
 }
 
 #[cfg(not(target_os = "linux"))]
 impl Misc for ATADevice<Device> {
 	fn ata_do(&self, dir: Direction, regs: &RegistersWrite) -> Result<(RegistersRead, Vec<u8>), Error> {
-		Ok(Self::ata_do(self, dir, regs)?)
+		let (regs_out, data) = Self::ata_do(self, dir, regs)?;
+		fetch_sense(self, regs.command, &regs_out)?;
+		Ok((regs_out, data))
+	}
+	fn ata_do_out(&self, regs: &RegistersWrite, data: &[u8], timeout: u32) -> Result<RegistersRead, Error> {
+		let regs_out = Self::ata_do_out(self, regs, data, timeout)?;
+		fetch_sense(self, regs.command, &regs_out)?;
+		Ok(regs_out)
 	}
 }
 impl Misc for ATADevice<SCSIDevice> {
 	fn ata_do(&self, dir: Direction, regs: &RegistersWrite) -> Result<(RegistersRead, Vec<u8>), Error> {
-		Ok(Self::ata_do(self, dir, regs)?)
+		let (regs_out, data) = Self::ata_do(self, dir, regs)?;
+		fetch_sense(self, regs.command, &regs_out)?;
+		Ok((regs_out, data))
+	}
+	fn ata_do_out(&self, regs: &RegistersWrite, data: &[u8], timeout: u32) -> Result<RegistersRead, Error> {
+		let regs_out = Self::ata_do_out(self, regs, data, timeout)?;
+		fetch_sense(self, regs.command, &regs_out)?;
+		Ok(regs_out)
 	}
 }
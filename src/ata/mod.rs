@@ -3,11 +3,12 @@ All things ATA.
 
 * Import [`ATADevice`](struct.ATADevice.html) to start sending ATA commands to the [`Device`](../device/index.html) or [`SCSIDevice`](../scsi/index.html).
 * Use [`data` module](data/index.html) to parse various low-level structures found in ATA command replies.
-* Import traits from porcelain modules (currently that's just [`misc`](misc/index.html)) to do typical tasks without needing to compose commands and parse responses yourself.
+* Import traits from porcelain modules ([`misc`](misc/index.html) and, for commands that might render device unusable or destroy user data, [`security`](security/index.html)) to do typical tasks without needing to compose commands and parse responses yourself.
 */
 
 pub mod data;
 pub mod misc;
+pub mod security;
 
 use crate::Direction;
 use crate::scsi::{self, SCSIDevice, SCSICommon};
@@ -15,7 +16,24 @@ use crate::scsi::{self, SCSIDevice, SCSICommon};
 #[derive(Debug, Clone, Copy)]
 pub enum Command {
 	Identify = 0xec,
+	IdentifyPacket = 0xa1,
 	SMART = 0xb0,
+	SetFeatures = 0xef,
+	CheckPowerMode = 0xe5,
+	StandbyImmediate = 0xe0,
+	IdleImmediate = 0xe1,
+	Standby = 0xe2,
+	Idle = 0xe3,
+	ReadNativeMaxAddress = 0xf8,
+	ReadNativeMaxAddressExt = 0x27,
+	AccessibleMaxAddressConfiguration = 0x78,
+	DeviceConfiguration = 0xb1,
+	ReadLogExt = 0x2f,
+	SecuritySetPassword = 0xf1,
+	SecurityErasePrepare = 0xf3,
+	SecurityEraseUnit = 0xf4,
+	SecurityFreezeLock = 0xf5,
+	RequestSenseDataExt = 0x0b,
 }
 #[derive(Debug, Clone, Copy)]
 pub enum SMARTFeature {
@@ -23,6 +41,61 @@ pub enum SMARTFeature {
 	ReadThresholds = 0xd1,
 	ReturnStatus = 0xda,
 }
+#[derive(Debug, Clone, Copy)]
+pub enum DCOFeature {
+	Restore = 0xc0,
+	FreezeLock = 0xc1,
+	Identify = 0xc2,
+	Set = 0xc3,
+}
+/// Subcommands of the ACCESSIBLE MAX ADDRESS CONFIGURATION command; unlike most commands, these go into the Features register, not the Features (15:8) one
+#[derive(Debug, Clone, Copy)]
+pub enum AMAFeature {
+	GetNativeMaxAddress = 0x00,
+	SetAccessibleMaxAddress = 0x01,
+	FreezeAccessibleMaxAddress = 0x02,
+}
+/// Subcommands of the SET FEATURES command, as written into the Features register
+#[derive(Debug, Clone, Copy)]
+pub enum SetFeature {
+	EnableWriteCache = 0x02,
+	EnableAPM = 0x05, // level goes into the Count register
+	EnableSATAFeature = 0x10, // see `SATAFeature`
+	EnableAAM = 0x42, // level goes into the Count register
+	DisableReadLookAhead = 0x55,
+	DisableWriteCache = 0x82,
+	DisableAPM = 0x85,
+	DisableSATAFeature = 0x90, // see `SATAFeature`
+	EnableReadLookAhead = 0xaa,
+	DisableAAM = 0xc2,
+	SenseDataReporting = 0xc3, // Count register: 1 to enable, 0 to disable
+}
+
+/// Serial ATA features that are enabled or disabled with `SetFeature::{Enable,Disable}SATAFeature`, as written into the Count register
+#[derive(Debug, Clone, Copy)]
+pub enum SATAFeature {
+	NonZeroBufferOffsets = 0x01,
+	DMASetupAutoActivation = 0x02,
+	DIPM = 0x03, // Device-Initiated Interface Power Management
+	InOrderDataDelivery = 0x04,
+	AsynchronousNotification = 0x05,
+	SoftwareSettingsPreservation = 0x06,
+	DeviceAutomaticPartialToSlumber = 0x07,
+	HardwareFeatureControl = 0x08,
+	DevSleep = 0x09,
+	HybridInformation = 0x0a,
+}
+
+/// High order bytes ("previous content") of the registers, only used by the commands from 48-bit Address feature set
+// features (15:8) are omitted since none of the commands implemented here need those
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RegistersHOB {
+	pub sector_count: u8,
+
+	pub sector: u8, // lba 31:24
+	pub cyl_low: u8, // lba 39:32
+	pub cyl_high: u8, // lba 47:40
+}
 
 // data port is omitted for obvious reasons
 #[derive(Debug)]
@@ -37,6 +110,27 @@ pub struct RegistersRead {
 	pub device: u8, // lba (most significant bits); aka drive/head, device/head, select
 
 	pub status: u8,
+
+	/// only present if the command was issued with `RegistersWrite.hob` set
+	pub hob: Option<RegistersHOB>,
+}
+impl RegistersRead {
+	/// Returns LBA the device put into the registers: either 48-bit one, if HOB registers are present, or 28-bit one, with bits 27:24 taken from the Device register.
+	pub fn lba(&self) -> u64 {
+		let low = ((self.cyl_high as u64) << 16)
+		        + ((self.cyl_low as u64) << 8)
+		        +  (self.sector as u64);
+		match self.hob {
+			Some(hob) =>
+				((hob.cyl_high as u64) << 40)
+				+ ((hob.cyl_low as u64) << 32)
+				+ ((hob.sector as u64) << 24)
+				+ low,
+			None =>
+				(((self.device & 0x0f) as u64) << 24)
+				+ low,
+		}
+	}
 }
 #[derive(Debug)]
 pub struct RegistersWrite {
@@ -50,6 +144,9 @@ pub struct RegistersWrite {
 	pub device: u8,
 
 	pub command: u8,
+
+	/// set this for the commands from 48-bit Address feature set, leave `None` otherwise
+	pub hob: Option<RegistersHOB>,
 }
 
 #[derive(Debug)]
@@ -84,6 +181,24 @@ macro_rules! ata_do { ($Err:ty) => {
 		}
 		ret
 	}
+
+	/// Issues PIO Data-Out command, transferring `data` to the device and waiting up to `timeout` seconds for the command to complete.
+	pub fn ata_do_out(&self, regs: &crate::ata::RegistersWrite, data: &[u8], timeout: u32) -> Result<crate::ata::RegistersRead, $Err> {
+		info!("issuing cmd: dir=To regs={:?} timeout={}", regs, timeout);
+		// N.B. data might contain passwords and such, hence no dumping it here
+
+		// this one is implemented in `mod {linux,freebsd}`, and here for `T: SCSIDevice`
+		let ret = Self::ata_platform_do_out(self, regs, data, timeout);
+		match &ret {
+			Ok(regs) => {
+				debug!("cmd reply: regs={:?}", regs);
+			},
+			err => {
+				debug!("cmd error: {:?}", err);
+			},
+		}
+		ret
+	}
 } }
 
 /*
@@ -104,6 +219,9 @@ impl ATADevice<SCSIDevice> {
 	fn ata_platform_do(&self, dir: Direction, regs: &RegistersWrite) -> Result<(RegistersRead, Vec<u8>), scsi::ATAError> {
 		self.device.ata_pass_through_16(dir, regs)
 	}
+	fn ata_platform_do_out(&self, regs: &RegistersWrite, data: &[u8], timeout: u32) -> Result<RegistersRead, scsi::ATAError> {
+		self.device.ata_pass_through_16_out(regs, data, timeout)
+	}
 
 	/// Return the wrapped device. Useful in cases when ATA PASS-THROUGH is used to determine whether this is an ATA device or not.
 	pub fn unwrap(self) -> SCSIDevice {
@@ -0,0 +1,188 @@
+/*!
+Security feature set commands that can lock the device or destroy all the data on it.
+
+These are deliberately kept out of [`misc`](../misc/index.html): one has to import [`Security`](trait.Security.html) explicitly to be able to issue any of them. Read-only status can be found in [`Id::security_status`](../data/id/struct.Id.html#structfield.security_status), and SECURITY FREEZE LOCK is available as [`Misc::security_freeze_lock()`](../misc/trait.Misc.html#method.security_freeze_lock).
+
+Typical erase sequence, also known as what `hdparm --security-set-pass` and `hdparm --security-erase` do:
+
+```no_run
+use hdd::Device;
+use hdd::ata::ATADevice;
+use hdd::ata::misc::Misc;
+use hdd::ata::security::{self, Security, Password};
+use hdd::ata::data::id::SecurityLevel;
+use hdd::scsi::SCSIDevice;
+
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let dev = ATADevice::new(SCSIDevice::new(Device::open("/dev/da0")?));
+
+let id = dev.get_device_id()?;
+let status = id.security_status.ok_or("Security feature set is not supported")?;
+if status.frozen {
+	return Err("security settings are frozen, power cycle the device".into());
+}
+
+let password = Password::user(b"erase-me").unwrap();
+dev.security_set_password(&password, SecurityLevel::High, None)?;
+dev.security_erase(&password, false, security::erase_timeout(&status, false))?;
+# Ok(())
+# }
+```
+*/
+
+use crate::ata::{RegistersWrite, Command};
+use crate::ata::misc::{self, Misc, check_status};
+use crate::ata::data::id::{SecurityLevel, SecurityStatus, EraseTime};
+
+/// Timeout for commands that are expected to complete immediately, in seconds.
+const TIMEOUT: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Identifier { User, Master }
+
+/// Password along with the identifier that tells which password it is.
+#[derive(Clone)]
+pub struct Password {
+	pub identifier: Identifier,
+	password: [u8; 32],
+}
+
+// make sure passwords never end up in logs
+impl ::std::fmt::Debug for Password {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		f.debug_struct("Password")
+			.field("identifier", &self.identifier)
+			.field("password", &"<hidden>")
+			.finish()
+	}
+}
+
+impl Password {
+	/// Returns `None` if `password` is longer than 32 bytes. Shorter passwords are padded with zeroes, the same way `hdparm` does that.
+	pub fn new(identifier: Identifier, password: &[u8]) -> Option<Self> {
+		if password.len() > 32 { return None; }
+
+		let mut padded = [0; 32];
+		padded[.. password.len()].copy_from_slice(password);
+
+		Some(Password { identifier, password: padded })
+	}
+	pub fn user(password: &[u8]) -> Option<Self> { Self::new(Identifier::User, password) }
+	pub fn master(password: &[u8]) -> Option<Self> { Self::new(Identifier::Master, password) }
+
+	// see ATA8-ACS, table 61 (SECURITY SET PASSWORD) and table 58 (SECURITY ERASE UNIT)
+	// word 0 is a control word that is filled by caller; words 1-16 are the password itself
+	fn to_block(&self, control: u16) -> Vec<u8> {
+		let mut block = vec![0; 512];
+
+		let control = control | match self.identifier {
+			Identifier::User => 0,
+			Identifier::Master => 1,
+		};
+		block[0] = (control & 0xff) as u8;
+		block[1] = (control >> 8) as u8;
+
+		// password is transferred as is, no byte swapping
+		block[2 .. 34].copy_from_slice(&self.password);
+
+		block
+	}
+}
+
+/**
+Returns the timeout for SECURITY ERASE UNIT command (in seconds), based on completion time estimated by the device.
+
+It is twice as long as the estimate; if the device does not provide one, or only tells that erase takes longer than some maximum value, timeout is at least one week.
+*/
+pub fn erase_timeout(status: &SecurityStatus, enhanced: bool) -> u32 {
+	let time = if enhanced { status.enhanced_erase_time } else { status.erase_time };
+	let week = 7 * 24 * 60 * 60;
+	match time {
+		Some(EraseTime::Minutes(minutes)) => minutes * 60 * 2,
+		Some(EraseTime::MoreThan(minutes)) => ::std::cmp::max(minutes * 60 * 2, week),
+		None => week,
+	}
+}
+
+/// See [module documentation](index.html).
+pub trait Security: Misc {
+	/**
+	Issues SECURITY SET PASSWORD command.
+
+	Setting user password enables the Security feature set: **the device will be locked after the next power cycle**, and will not be accessible until unlocked with the same password. `level` defines whether master password can also be used to unlock the device (`High`) or only to erase it (`Maximum`).
+
+	Setting master password does not enable the Security feature set; `master_password_id` is only used with master password.
+	*/
+	fn security_set_password(&self, password: &Password, level: SecurityLevel, master_password_id: Option<u16>) -> Result<(), misc::Error> {
+		info!("setting {:?} password, level={:?}", password.identifier, level);
+
+		let mut block = password.to_block(match level {
+			SecurityLevel::High => 0,
+			SecurityLevel::Maximum => 1 << 8,
+		});
+		if let (Identifier::Master, Some(id)) = (password.identifier, master_password_id) {
+			block[34] = (id & 0xff) as u8;
+			block[35] = (id >> 8) as u8;
+		}
+
+		let regs = self.ata_do_out(&RegistersWrite {
+			command: Command::SecuritySetPassword as u8,
+			features: 0,
+			sector_count: 1,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		}, &block, TIMEOUT)?;
+		check_status(&regs)
+	}
+
+	/// Issues SECURITY ERASE PREPARE command. Device aborts SECURITY ERASE UNIT unless this command is issued right before it; see also [`security_erase()`](#method.security_erase).
+	fn security_erase_prepare(&self) -> Result<(), misc::Error> {
+		info!("preparing to erase the device");
+
+		let (regs, _) = self.ata_do(crate::Direction::None, &RegistersWrite {
+			command: Command::SecurityErasePrepare as u8,
+			features: 0,
+			sector_count: 0,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		})?;
+		check_status(&regs)
+	}
+
+	/**
+	Issues SECURITY ERASE UNIT command, **irreversibly erasing all user data** and disabling the Security feature set (i.e. clearing the user password).
+
+	This command might take hours to complete, and it will be aborted by the OS unless `timeout` (in seconds) is long enough; see [`erase_timeout()`](fn.erase_timeout.html). In `enhanced` mode, device also overwrites reallocated sectors and such, see `SecurityStatus::enhanced_erase_supported`.
+	*/
+	fn security_erase_unit(&self, password: &Password, enhanced: bool, timeout: u32) -> Result<(), misc::Error> {
+		info!("erasing the device using {:?} password, enhanced={}", password.identifier, enhanced);
+
+		let block = password.to_block(if enhanced { 1 << 1 } else { 0 });
+
+		let regs = self.ata_do_out(&RegistersWrite {
+			command: Command::SecurityEraseUnit as u8,
+			features: 0,
+			sector_count: 1,
+			sector: 0,
+			cyl_low: 0,
+			cyl_high: 0,
+			device: 0,
+			hob: None,
+		}, &block, timeout)?;
+		check_status(&regs)
+	}
+
+	/// Issues SECURITY ERASE PREPARE and SECURITY ERASE UNIT commands, back to back. See [`security_erase_unit()`](#method.security_erase_unit).
+	fn security_erase(&self, password: &Password, enhanced: bool, timeout: u32) -> Result<(), misc::Error> {
+		self.security_erase_prepare()?;
+		self.security_erase_unit(password, enhanced, timeout)
+	}
+}
+
+impl<T: Misc + ?Sized> Security for T {}
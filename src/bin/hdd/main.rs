@@ -19,7 +19,7 @@ use hdd::{device, Device};
 use hdd::scsi::SCSIDevice;
 use hdd::ata::ATADevice;
 
-use hdd::ata::data::id;
+use hdd::ata::data::{id, id_packet};
 use hdd::drivedb;
 use hdd::ata::misc::{self, Misc};
 use hdd::scsi::ATAError;
@@ -63,8 +63,8 @@ static drivedb_additional_default: [&'static str; 1] = [
 	"/etc/smart_drivedb.h",
 ];
 
-/// Returns concatenated list of entries from main and additional drivedb files, falling back to built-in paths if none were provided.
-pub fn open_drivedb(options: Option<Vec<String>>) -> Option<drivedb::DriveDB> {
+/// Returns concatenated list of entries from main and additional drivedb files, falling back to built-in paths if none were provided, and to the embedded drivedb snapshot (if there's one) if no main file can be loaded.
+pub fn open_drivedb(options: Option<Vec<String>>, cache: Option<&String>) -> drivedb::DriveDB {
 	let options = options.unwrap_or_default();
 
 	let (paths_add, paths_main): (Vec<&str>, Vec<&str>) = options.iter()
@@ -75,11 +75,16 @@ pub fn open_drivedb(options: Option<Vec<String>>) -> Option<drivedb::DriveDB> {
 	let paths_add: Vec<&str> = paths_add.iter().map(|path| &path[1..]).collect();
 
 	let mut show_warn_add = true;
+	let mut show_warn_main = true;
 
 	// apply defaults if one of the lists is not provided
 	// also silence warnings for default additional file
 	let (paths_main, paths_add) = if paths_main.is_empty() {
 		let paths_main = drivedb_default.to_vec();
+		// smartmontools is not necessarily installed, and there's a built-in drivedb to fall back to anyway
+		if cfg!(feature = "embedded-drivedb") {
+			show_warn_main = false;
+		}
 
 		let paths_add = if paths_add.is_empty() {
 			show_warn_add = false;
@@ -94,6 +99,9 @@ pub fn open_drivedb(options: Option<Vec<String>>) -> Option<drivedb::DriveDB> {
 	};
 
 	let mut loader = drivedb::Loader::new();
+	if let Some(dir) = cache {
+		loader.use_cache(dir);
+	}
 
 	for f in paths_add {
 		match loader.load_additional(f) {
@@ -104,17 +112,26 @@ pub fn open_drivedb(options: Option<Vec<String>>) -> Option<drivedb::DriveDB> {
 		}
 	}
 
-	for f in paths_main {
-		match loader.load(f) {
-			Ok(()) => {
-				break; // we only need one 'main' file, the first valid one
-			},
-			Err(e) => eprint!("Cannot open drivedb file {}: {}\n", f, e),
-		}
+	// we only need one 'main' file, the first valid one
+	#[cfg_attr(not(feature = "embedded-drivedb"), allow(unused_variables))]
+	let main_loaded = paths_main.iter().any(|f| match loader.load(f) {
+		Ok(()) => true,
+		Err(e) => {
+			if show_warn_main {
+				eprint!("Cannot open drivedb file {}: {}\n", f, e);
+			}
+			false
+		},
+	});
+
+	// built-in snapshot is only a fallback, so that files from the system or from `-B` take precedence
+	#[cfg(feature = "embedded-drivedb")]
+	if !main_loaded {
+		loader.load_builtin();
 	}
 
-	// TODO? show regex error to the world
-	loader.db().ok()
+	// invalid entries are reported by the library through `log`
+	loader.db()
 }
 
 #[cfg(target_os = "linux")]
@@ -153,11 +170,71 @@ impl ::std::str::FromStr for Type {
 	}
 }
 
+/// Low-power states, from the deepest one; used both to describe current state of the device and to set `--nocheck` threshold
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum LowPower { Sleep, Standby, Idle }
+
+impl LowPower {
+	fn name(&self) -> &'static str {
+		match self {
+			LowPower::Sleep => "sleep",
+			LowPower::Standby => "standby",
+			LowPower::Idle => "idle",
+		}
+	}
+}
+
+/// Exit code for the case when `--nocheck` prevented us from querying the device
+const EXIT_NOCHECK: i32 = 2;
+
+/**
+Checks whether the device is in a power mode that `nocheck` asks to leave undisturbed, and if so, exits with `EXIT_NOCHECK`.
+
+Returns `Err(_)` if device does not support ATA PASS-THROUGH, which callers might use to tell SCSI devices from ATA ones.
+
+Failed CHECK POWER MODE is only taken as a sign of sleep if the device is already known to be `ata`; otherwise it might just as well be a SCSI device that rejects ATA PASS-THROUGH in some other way, and it's up to the caller to find out.
+*/
+fn skip_if_low_power(dev: &dyn Misc, nocheck: Option<LowPower>, ata: bool, json: bool) -> Result<(), misc::Error> {
+	let nocheck = match nocheck {
+		Some(nocheck) => nocheck,
+		None => return Ok(()),
+	};
+
+	let state = match dev.check_power_mode() {
+		Ok(mode) if mode.is_standby() => Some(LowPower::Standby),
+		Ok(mode) if mode.is_idle() => Some(LowPower::Idle),
+		Ok(_) => None,
+		Err(e @ misc::Error::SCSI(ATAError::NotSupported)) => return Err(e),
+		Err(e) if !ata => {
+			log::info!("CHECK POWER MODE failed, device type is not known yet: {}", e);
+			return Ok(());
+		},
+		// just like smartctl, assume that device does not respond because it is sleeping
+		Err(e) => {
+			eprint!("CHECK POWER MODE failed, assuming device is in sleep mode: {}\n", e);
+			Some(LowPower::Sleep)
+		},
+	};
+
+	if let Some(state) = state.filter(|&state| state <= nocheck) {
+		if json {
+			print!("{{\"power_mode\":\"{}\",\"skipped\":true}}\n", state.name());
+		} else {
+			eprint!("Device is in {} mode, skipping checks\n", state.name().to_uppercase());
+		}
+		::std::process::exit(EXIT_NOCHECK);
+	}
+
+	Ok(())
+}
+
 #[derive(Debug)]
 pub enum DeviceArgument {
 	#[cfg(not(target_os = "linux"))]
 	ATA(ATADevice<Device>, id::Id),
 	SAT(ATADevice<SCSIDevice>, id::Id),
+	/// optical and tape drives, CompactFlash with PACKET feature set etc., behind SAT
+	ATAPI(ATADevice<SCSIDevice>, id_packet::PacketId),
 	SCSI(SCSIDevice),
 }
 
@@ -191,6 +268,13 @@ fn main() {
 			.action(ArgAction::Count)
 			.help("Verbose output: set once to log actions, twice to also show raw data buffers\ncan also be set though env_logger's RUST_LOG env")
 		)
+		.arg(Arg::new("nocheck")
+			.short('n') // smartctl-like
+			.long("nocheck") // smartctl-like
+			.value_parser(["never", "sleep", "standby", "idle"])
+			.default_value("never")
+			.help("do not query ATA devices that are in this or deeper low-power mode, so that they would not spin up;\nexit with code 2 instead (JSON output gets '\"skipped\": true' marker)")
+		)
 		/*
 		Unlike other pretty common arguments like `--json`, and unlike in tools like `smartctl`, `device` appears before the subcommand.
 		Sure this is surprising and "counterintuitive" for users, but there are reasons to do so:
@@ -232,14 +316,28 @@ fn main() {
 		.unwrap_or("auto")
 		.parse::<Type>().unwrap();
 
+	let nocheck = match args.get_one::<String>("nocheck").map(|s| s.as_str()) {
+		Some("sleep") => Some(LowPower::Sleep),
+		Some("standby") => Some(LowPower::Standby),
+		Some("idle") => Some(LowPower::Idle),
+		_ => None,
+	};
+
 	let (subcommand, sargs) = args.subcommand().unwrap();
 	// unwrap() ×2: clap should not allow subcommands that do not exist
 	let subcommand = SUBCOMMANDS.get(subcommand).unwrap();
 
+	// not every subcommand has these args
+	let json = sargs.try_get_one::<bool>("json").ok().flatten() == Some(&true)
+		|| sargs.try_get_one::<String>("format").ok().flatten().map(|s| s.as_str()) == Some("json");
+
 	/*
 	Why do we issue ATA IDENTIFY DEVICE here?
 	- Device id is what every subcommand uses for one reason or the other, but usually to check whether some feature is supported and enabled.
 	- It allows us to distinguish between pure SCSI devices and ATA devices behind SAT by issuing ATA PASS-THROUGH and checking whether this command is supported.
+	- ATAPI devices abort IDENTIFY DEVICE, which is how we know it's time to try IDENTIFY PACKET DEVICE.
+
+	If `--nocheck` is set, CHECK POWER MODE goes first though, as IDENTIFY DEVICE might spin up the drive.
 	*/
 
 	let dev = dev.map(|dev| match dtype {
@@ -248,23 +346,27 @@ fn main() {
 				device::Type::SCSI => {
 					// check whether devices replies to ATA PASS-THROUGH
 					let satdev = ATADevice::new(SCSIDevice::new(dev));
-					match satdev.get_device_id() {
+					match skip_if_low_power(&satdev, nocheck, false, json).and_then(|_| satdev.get_device_id()) {
 						// this is really an ATA device
 						Ok(id) =>
 							DeviceArgument::SAT(satdev, id),
 						// nnnnope, plain SCSI
 						Err(misc::Error::SCSI(ATAError::NotSupported)) =>
 							DeviceArgument::SCSI(satdev.unwrap()),
-						// unexpected errors: warn and continue as plain SCSI
-						Err(e) => {
-							eprint!("ATA PASS-THROUGH probe failed (treating as SCSI): {}\n", e);
-							DeviceArgument::SCSI(satdev.unwrap())
+						// either ATAPI device, or some unexpected error; in the latter case warn and continue as plain SCSI
+						Err(e) => match satdev.get_packet_device_id() {
+							Ok(id) => DeviceArgument::ATAPI(satdev, id),
+							Err(_) => {
+								eprint!("ATA PASS-THROUGH probe failed (treating as SCSI): {}\n", e);
+								DeviceArgument::SCSI(satdev.unwrap())
+							},
 						},
 					}
 				},
 				#[cfg(not(target_os = "linux"))]
 				device::Type::ATA => {
 					let atadev = ATADevice::new(dev);
+					skip_if_low_power(&atadev, nocheck, true, json).unwrap();
 					let id = atadev.get_device_id().unwrap();
 					DeviceArgument::ATA(atadev, id)
 				},
@@ -273,13 +375,21 @@ fn main() {
 		#[cfg(target_os = "freebsd")]
 		Type::ATA => {
 			let dev = ATADevice::new(dev);
+			skip_if_low_power(&dev, nocheck, true, json).unwrap();
 			let id = dev.get_device_id().unwrap();
 			DeviceArgument::ATA(dev, id)
 		},
 		Type::SAT => {
 			let dev = ATADevice::new(SCSIDevice::new(dev));
-			let id = dev.get_device_id().unwrap();
-			DeviceArgument::SAT(dev, id)
+			skip_if_low_power(&dev, nocheck, true, json).unwrap();
+			match dev.get_device_id() {
+				Ok(id) => DeviceArgument::SAT(dev, id),
+				Err(e) => match dev.get_packet_device_id() {
+					Ok(id) => DeviceArgument::ATAPI(dev, id),
+					// report the original error, not the one from the ATAPI fallback
+					Err(_) => panic!("cannot identify device: {}", e),
+				},
+			}
 		},
 		Type::SCSI => DeviceArgument::SCSI(SCSIDevice::new(dev)),
 	});
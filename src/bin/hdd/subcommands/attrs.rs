@@ -1,12 +1,14 @@
 use hdd::ata::misc::Misc;
 
 use hdd::ata::data::attr;
+use hdd::ata::data::smart_data;
 use hdd::ata::data::attr::raw::Raw;
 use hdd::ata::data::id;
 use hdd::drivedb;
 use hdd::drivedb::vendor_attribute;
+use hdd::metrics;
 
-use hdd::scsi::pages::{SCSIPages, ErrorCounter};
+use hdd::scsi::pages::{SCSIPages, ErrorCounter, ParameterValue};
 use hdd::scsi::{SCSICommon, DefectList};
 use hdd::scsi::data::inquiry;
 
@@ -30,7 +32,7 @@ use prettytable;
 use prettytable::{Table, Row, Cell};
 
 use crate::{DeviceArgument, open_drivedb};
-use super::{Subcommand, arg_drivedb};
+use super::{Subcommand, arg_drivedb, arg_drivedb_cache};
 
 fn to_json_value<T: serde::Serialize>(value: &T) -> serde_json::Value {
 	serde_json::to_value(value).unwrap()
@@ -65,11 +67,12 @@ fn print_attributes(values: Vec<attr::SmartAttribute>) {
 			val.value.map(|v| format!("{:3}", v)).unwrap_or("---".to_string()),
 			val.worst.map(|v| format!("{:3}", v)).unwrap_or("---".to_string()),
 			val.thresh.map(|v| format!("{:3}", v)).unwrap_or("(?)".to_string()),
-			match (val.value, val.worst, val.thresh) {
-				(Some(v), _, Some(t)) if v <= t => "NOW ",
-				(_, Some(w), Some(t)) if w <= t => "past",
+			match val.state() {
+				attr::AttributeState::FailingNow => "NOW ",
+				attr::AttributeState::FailedInPast => "past",
+				attr::AttributeState::InvalidThreshold => "inv?",
 				// either value/worst are part of the `val.row`,
-				// or threshold is not available,
+				// or threshold is not available (or is 'always passing'),
 				// or value never was below the threshold
 				_ => "-   ",
 			},
@@ -86,6 +89,50 @@ fn print_attributes(values: Vec<attr::SmartAttribute>) {
 	print!("                             P prefailure warning\n");
 }
 
+fn print_verdict(verdict: attr::Verdict) {
+	use self::attr::Verdict::*;
+	print!("\nAttributes verdict: {}\n", match verdict {
+		Good => "good",
+		FailedInPast => "some attributes were failing in the past",
+		OldAgeFailing => "old-age attributes are FAILING NOW, device has probably exceeded its design life",
+		PreFailFailing => "pre-failure attributes are FAILING NOW, device failure is predicted within 24 hours",
+	});
+}
+
+fn minutes(time: Option<u16>) -> String {
+	match time {
+		Some(time) => format!("{} min", time),
+		None => "not supported".to_string(),
+	}
+}
+
+fn print_smart_data(data: &smart_data::SmartData) {
+	if !data.checksum_valid {
+		print!("WARNING: S.M.A.R.T. data checksum is invalid, information below might be corrupted\n\n");
+	}
+
+	print!("Off-line data collection: {}{}\n",
+		data.offline_status,
+		if data.offline_auto_enabled { ", automatic collection enabled" } else { "" },
+	);
+	if data.capabilities.offline_immediate {
+		print!("Off-line data collection time: {} s\n", data.offline_time);
+	}
+	print!("Self-test status: {}", data.self_test_status);
+	if data.self_test_status == smart_data::SelfTestStatus::InProgress {
+		print!(", {}% remaining", data.self_test_remaining);
+	}
+	print!("\n");
+	print!("Self-test polling time: short {}, extended {}, conveyance {}\n",
+		minutes(data.short_self_test_time),
+		minutes(data.extended_self_test_time),
+		minutes(data.conveyance_self_test_time),
+	);
+	print!("Selective self-test: {}\n", if data.capabilities.selective_self_test { "supported" } else { "not supported" });
+	print!("Error logging: {}\n", if data.error_logging_supported { "supported" } else { "not supported" });
+	print!("\n");
+}
+
 fn escape(s: &String) -> String {
 	s.chars()
 		.flat_map(|c| c.escape_default())
@@ -114,6 +161,8 @@ fn format_prom<T: ToString>(key: &str, labels: &HashMap<&str, String>, value: T)
 
 fn print_prometheus_values(labels: &HashMap<&str, String>, values: Vec<attr::SmartAttribute>) {
 	for val in values {
+		let state = val.state();
+
 		let mut labels = labels.clone();
 		labels.insert("id", val.id.to_string());
 		labels.insert("name", val.name.unwrap_or("?".to_string()));
@@ -122,6 +171,14 @@ fn print_prometheus_values(labels: &HashMap<&str, String>, values: Vec<attr::Sma
 		val.value.map(|v| print!("{}\n", format_prom("smart_value", &labels, v)));
 		val.worst.map(|v| print!("{}\n", format_prom("smart_worst", &labels, v)));
 		val.thresh.map(|v| print!("{}\n", format_prom("smart_thresh", &labels, v)));
+		match state {
+			attr::AttributeState::Ok | attr::AttributeState::FailingNow | attr::AttributeState::FailedInPast => {
+				print!("{}\n", format_prom("smart_failing_now", &labels, (state == attr::AttributeState::FailingNow) as u8));
+				print!("{}\n", format_prom("smart_failed_in_past", &labels, (state == attr::AttributeState::FailedInPast) as u8));
+			},
+			// there's nothing to compare value against
+			_ => (),
+		}
 		print!("{}\n", format_prom("smart_raw", &labels, {
 			use self::Raw::*;
 			match val.raw {
@@ -129,23 +186,43 @@ fn print_prometheus_values(labels: &HashMap<&str, String>, values: Vec<attr::Sma
 				Raw8(_) => NAN,
 				Raw16(_) => NAN,
 				Raw64(x) => x as f64,
+				Hex(x, _) => x as f64,
 				// TODO show opt value somehow?
 				Raw16opt16(x, _) => x as f64,
 				Raw16avg16 { value, .. } => value as f64,
 				Raw24opt8(x, _) => x as f64,
 				// TODO show div value somehow?
 				Raw24div(x, _) => x as f64,
-				Minutes(x) => x as f64,
+				Minutes(x, _) => x as f64,
 				Seconds(x) => x as f64,
 				HoursMilliseconds(h, ms) => (h as f64) * 3600. + (ms as f64) / 1000.,
 				Celsius(x) => x as f64,
 				// if you're exporting this into your monitoring system you already do not care about min and max that this drive reports
 				CelsiusMinMax { current, .. } => current as f64,
+				CelsiusMinMaxCount { current, .. } => current as f64,
 			}
 		}));
 	}
 }
 
+fn metrics_json(metrics: &[metrics::Metric]) -> serde_json::Value {
+	let mut json = serde_json::Map::new();
+	for metric in metrics {
+		let mut tmp = serde_json::Map::new();
+		tmp.insert("value".to_string(), to_json_value(&metric.value));
+		tmp.insert("unit".to_string(), to_json_value(&metric.kind.unit().name()));
+		tmp.insert("source".to_string(), to_json_value(&metric.source));
+		json.insert(metric.kind.key().to_string(), serde_json::Value::Object(tmp));
+	}
+	serde_json::Value::Object(json)
+}
+
+fn print_prometheus_metrics(labels: &HashMap<&str, String>, metrics: &[metrics::Metric]) {
+	for metric in metrics {
+		print!("{}\n", format_prom(&format!("drive_{}", metric.kind.key()), labels, metric.value));
+	}
+}
+
 pub struct Attrs {}
 impl Subcommand for Attrs {
 	fn subcommand(&self) -> Command {
@@ -168,7 +245,13 @@ impl Subcommand for Attrs {
 				// for consistency with other subcommands
 				.help("alias for --format=json")
 			)
+			.arg(Arg::new("metrics")
+				.long("metrics")
+				.action(clap::ArgAction::SetTrue)
+				.help("add vendor-neutral metrics to json output;\nfor ATA devices, this turns the list of attributes into {\"attributes\": [...], \"metrics\": {...}} object")
+			)
 			.arg(arg_drivedb())
+			.arg(arg_drivedb_cache())
 			.arg(Arg::new("vendorattribute")
 				.action(clap::ArgAction::Append)
 				.short('v') // smartctl-like
@@ -213,15 +296,21 @@ impl Subcommand for Attrs {
 			.into_iter()
 			.filter_map(|attr| vendor_attribute::parse(attr).ok()) // TODO Err(_)
 			.collect();
+		let with_metrics = args.get_flag("metrics");
 		let drivedb = open_drivedb(args.get_many::<String>("drivedb")
-			.map(|vals| vals.map(|v| v.to_string()).collect()));
+			.map(|vals| vals.map(|v| v.to_string()).collect()),
+			args.get_one::<String>("drivedb-cache"));
 
 		use crate::DeviceArgument::*;
 		match dev {
 			#[cfg(not(target_os = "linux"))]
-			dev @ ATA(_, _) => attrs_ata(path, dev, format, drivedb, user_attributes),
-			dev @ SAT(_, _) => attrs_ata(path, dev, format, drivedb, user_attributes),
-			dev @ SCSI(_) => attrs_scsi(path, dev, format),
+			dev @ ATA(_, _) => attrs_ata(path, dev, format, with_metrics, &drivedb, user_attributes),
+			dev @ SAT(_, _) => attrs_ata(path, dev, format, with_metrics, &drivedb, user_attributes),
+			ATAPI(_, _) => {
+				eprint!("S.M.A.R.T. attributes are not available for ATAPI devices\n");
+				::std::process::exit(1);
+			},
+			dev @ SCSI(_) => attrs_scsi(path, dev, format, with_metrics, &drivedb),
 		};
 	}
 }
@@ -230,15 +319,16 @@ impl Subcommand for Attrs {
 enum Format { Plain, JSON, Prometheus }
 use self::Format::*;
 
-fn attrs_ata(path: &str, dev: &DeviceArgument, format: Format, drivedb: Option<drivedb::DriveDB>, user_attributes: Vec<drivedb::Attribute>) {
+fn attrs_ata(path: &str, dev: &DeviceArgument, format: Format, with_metrics: bool, drivedb: &drivedb::DriveDB, user_attributes: Vec<drivedb::Attribute>) {
 	let id = match dev {
 		#[cfg(not(target_os = "linux"))]
 		DeviceArgument::ATA(_, id) => id,
 		DeviceArgument::SAT(_, id) => id,
+		DeviceArgument::ATAPI(_, _) => unreachable!(),
 		DeviceArgument::SCSI(_) => unreachable!(),
 	};
 
-	let dbentry = drivedb.as_ref().map(|drivedb| drivedb.render_meta(
+	let dbentry = Some(drivedb.render_meta(
 		&id,
 		&user_attributes,
 	));
@@ -249,7 +339,7 @@ fn attrs_ata(path: &str, dev: &DeviceArgument, format: Format, drivedb: Option<d
 	labels.insert("model", id.model.clone());
 	labels.insert("serial", id.serial.clone());
 	if let Some(entry) = &dbentry {
-		if let Some(family) = entry.family {
+		if let Some(family) = &entry.family {
 			labels.insert("family", family.clone());
 		}
 	};
@@ -273,23 +363,50 @@ fn attrs_ata(path: &str, dev: &DeviceArgument, format: Format, drivedb: Option<d
 			print!("{}\n", format_prom("smart_enabled", &labels, 0)),
 
 		(format, Enabled) => {
-			let values = match dev {
+			let dev: &dyn Misc = match dev {
 				#[cfg(not(target_os = "linux"))]
-				DeviceArgument::ATA(dev, _) => dev.get_smart_attributes(&dbentry).unwrap(),
-				DeviceArgument::SAT(dev, _) => dev.get_smart_attributes(&dbentry).unwrap(),
+				DeviceArgument::ATA(dev, _) => dev,
+				DeviceArgument::SAT(dev, _) => dev,
+				DeviceArgument::ATAPI(_, _) => unreachable!(),
 				DeviceArgument::SCSI(_) => unreachable!(),
 			};
+			let data = dev.get_smart_data(&dbentry).unwrap();
+
+			// only needed for vendor-neutral metrics, which are not shown in plain format, and only shown in json if asked to
+			let metrics = if format == Prometheus || (format == JSON && with_metrics) {
+				let stats = if id.gp_logging_supported {
+					dev.get_device_statistics().ok()
+				} else { None };
+				metrics::ata(&data.attributes, stats.as_ref(), id.sector_size_log)
+			} else { vec![] };
 
 			match format {
-				Plain => print_attributes(values),
+				Plain => {
+					print_smart_data(&data);
+					let verdict = attr::verdict(&data.attributes);
+					let has_attributes = !data.attributes.is_empty();
+					print_attributes(data.attributes);
+					if has_attributes {
+						print_verdict(verdict);
+					}
+				},
+				JSON if with_metrics => {
+					let mut json = serde_json::Map::new();
+					json.insert("attributes".to_string(), to_json_value(&data.attributes));
+					json.insert("metrics".to_string(), metrics_json(&metrics));
+					print!("{}\n", serde_json::to_string(&json).unwrap());
+				},
 				JSON => print!("{}\n",
 					serde_json::to_string(
-						&to_json_value(&values)
+						&to_json_value(&data.attributes)
 					).unwrap()
 				),
 				Prometheus => {
 					print!("{}\n", format_prom("smart_enabled", &labels, 1));
-					print_prometheus_values(&labels, values);
+					// 0: good, 1: some attributes failed in the past, 2: old-age attributes are failing now, 3: pre-failure attributes are failing now
+					print!("{}\n", format_prom("smart_attributes_verdict", &labels, attr::verdict(&data.attributes) as u8));
+					print_prometheus_values(&labels, data.attributes);
+					print_prometheus_metrics(&labels, &metrics);
 				},
 			}
 		},
@@ -469,7 +586,7 @@ fn print_human_scsi_error_counters(counters: &Vec<(&str, HashMap<ErrorCounter, u
 
 // TODO other formats
 // TODO prometheus: device id labels, just like in attrs_ata
-fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
+fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format, with_metrics: bool, drivedb: &drivedb::DriveDB) {
 	if path.starts_with("/dev/nvme") || path.starts_with("nvme") {
 		eprint!("NVMe devices are not supported by this tool; use nvme-cli instead.\n");
 		return;
@@ -479,6 +596,7 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 		#[cfg(not(target_os = "linux"))]
 		DeviceArgument::ATA(_, _) => unreachable!(),
 		DeviceArgument::SAT(_, _) => unreachable!(),
+		DeviceArgument::ATAPI(_, _) => unreachable!(),
 		DeviceArgument::SCSI(dev) => dev,
 	};
 
@@ -489,13 +607,28 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 
 	let mut json = serde_json::Map::new();
 
+	// what vendor-neutral metrics are derived from
+	let mut write_error_counters = None;
+	let mut temperature = None;
+	let mut grown_defects = None;
+
 	let mut labels = HashMap::new();
 	labels.insert("dev", path.to_string());
+	let mut dbentry = None;
 	if let Ok((_sense, data)) = dev.scsi_inquiry(false, 0) {
 		let inquiry = inquiry::parse_inquiry(&data);
 		labels.insert("vendor", inquiry.vendor_id.clone());
 		labels.insert("model", inquiry.product_id.clone());
 		labels.insert("firmware", inquiry.product_rev.clone());
+		dbentry = Some(drivedb.render_scsi_meta(&inquiry));
+	}
+	if let Some(entry) = &dbentry {
+		if let Some(family) = &entry.family {
+			labels.insert("family", family.clone());
+		}
+		if entry.warning.is_some() && format == Prometheus {
+			print!("{}\n", format_prom("smart_drivedb_warning", &labels, 1));
+		}
 	}
 
 	if let Ok(ref mut pages) = pages {
@@ -506,6 +639,9 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 			("read-reverse", pages.read_reverse_error_counters().ok()),
 			("verify",       pages.verify_error_counters().ok()),
 		];
+		write_error_counters = error_counters.iter()
+			.find(|(name, _)| *name == "write")
+			.and_then(|(_, counters)| counters.clone());
 
 		match format {
 			Prometheus => {
@@ -551,6 +687,7 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 
 	// also TODO Err()
 	if let Some(Ok((temp, ref_temp))) = pages.iter_mut().next().map(|p| p.temperature()) {
+		temperature = temp;
 		match format {
 			Prometheus => {
 				if let Some(t) = temp     { print!("{}\n", format_prom("scsi_temperature", &labels, t)) };
@@ -630,6 +767,7 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 
 	// again, TODO Err()
 	if let Ok(Some(defects)) = dev.read_defect_data_10(DefectList::Grown) {
+		grown_defects = Some(defects as u32);
 		match format {
 			Prometheus => {
 				labels.insert("list", "grown".to_string());
@@ -647,6 +785,65 @@ fn attrs_scsi(path: &str, dev: &DeviceArgument, format: Format) {
 		}
 	}
 
+	// Vendor-specific log parameters, as described by drivedb
+
+	if let (Some(entry), Ok(pages)) = (&dbentry, &mut pages) {
+		let params = pages.described_parameters(entry);
+		match format {
+			Prometheus => {
+				for (desc, value) in params {
+					// there's no way to represent strings or raw bytes as a metric
+					if let ParameterValue::Uint(x) = value {
+						let mut labels = labels.clone();
+						labels.insert("page", format!("{}", desc.page));
+						labels.insert("param", format!("{}", desc.code));
+						if let Some(name) = desc.name {
+							labels.insert("name", name);
+						}
+						print!("{}\n", format_prom("scsi_log_parameter", &labels, x));
+					}
+				}
+			},
+			Plain => {
+				if !params.is_empty() {
+					print!("\nLog parameters described by drive database:\n");
+				}
+				for (desc, value) in params {
+					print!("  page 0x{:02x}, param 0x{:04x} {:<24} {}\n",
+						desc.page, desc.code, desc.name.as_deref().unwrap_or("?"), value);
+				}
+			},
+			JSON => {
+				let params: Vec<_> = params.iter().map(|(desc, value)| {
+					let mut tmp = serde_json::Map::new();
+					tmp.insert("page".to_string(), to_json_value(&desc.page));
+					tmp.insert("param".to_string(), to_json_value(&desc.code));
+					tmp.insert("name".to_string(), to_json_value(&desc.name));
+					tmp.insert("value".to_string(), to_json_value(value));
+					serde_json::Value::Object(tmp)
+				}).collect();
+				json.insert("log-parameters".to_string(), serde_json::Value::Array(params));
+			},
+		}
+	}
+
+	// Vendor-neutral metrics
+
+	if format == Prometheus || (format == JSON && with_metrics) {
+		let metrics = metrics::scsi(&metrics::SCSISources {
+			write_error_counters: write_error_counters.as_ref(),
+			temperature,
+			power_on_minutes: pages.iter_mut().next().and_then(|p| p.power_on_minutes().ok()),
+			percentage_used: pages.iter_mut().next().and_then(|p| p.percentage_used().ok()),
+			grown_defects,
+		});
+		match format {
+			Prometheus => print_prometheus_metrics(&labels, &metrics),
+			JSON => { json.insert("metrics".to_string(), metrics_json(&metrics)); },
+			Plain => (),
+		}
+	}
+
 	if format == JSON {
 		print!("{}\n", serde_json::to_string(&json).unwrap());
 	}
@@ -0,0 +1,214 @@
+use hdd::drivedb::{self, vendor_attribute};
+
+use clap::{
+	Arg,
+	ArgMatches,
+	Command,
+};
+
+use crate::{DeviceArgument, open_drivedb};
+use super::{Subcommand, arg_json, arg_drivedb, arg_drivedb_cache};
+
+use std::path::Path;
+
+fn lint(db: &drivedb::DriveDB, use_json: bool) {
+	let issues = db.lint();
+
+	if use_json {
+		print!("{}\n", serde_json::to_string(&issues).unwrap());
+	} else if issues.is_empty() {
+		print!("No problems found\n");
+	} else {
+		for issue in issues.iter() {
+			print!("{}\n", issue);
+		}
+	}
+
+	if !issues.is_empty() {
+		::std::process::exit(1);
+	}
+}
+
+fn print_meta(meta: &drivedb::DriveMeta) {
+	match &meta.entry {
+		Some(entry) => {
+			print!("Model family: {}\n", entry.family);
+			// unwrap: only the default entry has no index
+			print!("Matched entry #{} at {}\n", entry.index.unwrap(), entry);
+		},
+		None => print!("No matching entry\n"),
+	}
+	match &meta.default {
+		Some(default) => print!("Default entry at {}\n", default),
+		None => print!("No default entry\n"),
+	}
+
+	if let Some(warning) = &meta.warning {
+		print!("\n══════ WARNING ══════\n{}\n═════════════════════\n", warning);
+	}
+
+	if !meta.firmware_bugs().is_empty() {
+		print!("Firmware bugs: {}\n", meta.firmware_bugs().iter()
+			.map(|bug| format!("{:?}", bug))
+			.collect::<Vec<_>>()
+			.join(", "));
+	}
+
+	if !meta.log_parameters().is_empty() {
+		print!("Log parameters:\n");
+		for param in meta.log_parameters() {
+			print!("  page 0x{:02x}, param 0x{:04x}: {:?} {}\n",
+				param.page, param.code, param.format, param.name.as_deref().unwrap_or("?"));
+		}
+	}
+
+	for entry in meta.default.iter().chain(meta.entry.iter()) {
+		for preset in entry.invalid_presets.iter() {
+			print!("Invalid preset in entry at {}, ignored: {}\n", entry, preset);
+		}
+	}
+}
+
+fn print_attributes(attrs: &[(u8, vendor_attribute::Attribute)]) {
+	print!("\n{:>3} {:<32} {:<14} {:<10} {:<4}\n", "ID", "Name", "Format", "Byte order", "Type");
+	for (id, attr) in attrs {
+		print!("{:>3} {:<32} {:<14} {:<10} {:<4}\n",
+			id,
+			attr.name.as_deref().unwrap_or("?"),
+			// this is how these formats are written in drivedb
+			format!("{}{}", attr.format, if attr.increasing { "+" } else { "" }),
+			attr.byte_order,
+			match attr.drivetype {
+				Some(vendor_attribute::Type::HDD) => "HDD",
+				Some(vendor_attribute::Type::SSD) => "SSD",
+				None => "",
+			},
+		);
+	}
+}
+
+fn lookup(db: &drivedb::DriveDB, args: &ArgMatches, explain: bool, use_json: bool) {
+	let model = args.get_one::<String>("model").unwrap_or_else(|| {
+		eprint!("<model> is required\n");
+		::std::process::exit(1);
+	});
+	let firmware = args.get_one::<String>("firmware").map(|s| s.as_str()).unwrap_or("");
+	let drivetype = match args.get_one::<String>("drivetype").map(|s| s.as_str()) {
+		Some("hdd") => Some(vendor_attribute::Type::HDD),
+		Some("ssd") => Some(vendor_attribute::Type::SSD),
+		_ => None,
+	};
+
+	let user_attributes: Vec<_> = args.get_many::<String>("vendorattribute")
+		.map(|attrs| attrs.map(|attr| attr.as_str()).collect::<Vec<_>>())
+		.unwrap_or_default()
+		.into_iter()
+		.filter_map(|attr| vendor_attribute::parse(attr).ok()) // TODO Err(_)
+		.collect();
+
+	let meta = match args.get_one::<String>("interface").map(|s| s.as_str()) {
+		Some("scsi") => {
+			// vendor identification is not supposed to contain spaces, product identification might
+			let (vendor, product) = model.split_once(' ').unwrap_or((model, ""));
+			db.lookup_scsi(vendor, product, firmware)
+		},
+		Some("nvme") => db.lookup_nvme(model, firmware),
+		_ => db.lookup(model, firmware, drivetype, &user_attributes),
+	};
+
+	// what every attribute would look like, the same way `attrs` renders them
+	let attrs: Vec<_> = if explain {
+		(1 ..= 255).filter_map(|id| meta.render_attribute(id).map(|attr| (id, attr))).collect()
+	} else { vec![] };
+
+	if use_json {
+		let mut json = serde_json::Map::new();
+		json.insert("family".to_string(), serde_json::to_value(&meta.family).unwrap());
+		json.insert("warning".to_string(), serde_json::to_value(&meta.warning).unwrap());
+		json.insert("entry".to_string(), serde_json::to_value(&meta.entry).unwrap());
+		json.insert("default".to_string(), serde_json::to_value(&meta.default).unwrap());
+		json.insert("firmware_bugs".to_string(), serde_json::to_value(meta.firmware_bugs()).unwrap());
+		json.insert("log_parameters".to_string(), serde_json::to_value(meta.log_parameters()).unwrap());
+		if explain {
+			// catch-all presets (e.g. `-v N,raw48`) have no id of their own
+			let attrs: Vec<_> = attrs.iter().map(|(id, attr)| drivedb::Attribute { id: Some(*id), ..attr.clone() }).collect();
+			json.insert("attributes".to_string(), serde_json::to_value(&attrs).unwrap());
+		}
+		print!("{}\n", serde_json::to_string(&json).unwrap());
+	} else {
+		print_meta(&meta);
+		if explain {
+			print_attributes(&attrs);
+		}
+	}
+}
+
+pub struct DriveDB {}
+impl Subcommand for DriveDB {
+	fn subcommand(&self) -> Command {
+		Command::new("drivedb")
+			.about("Checks drive database, or shows what it knows about some drive model")
+			.arg(Arg::new("action")
+				.required(true)
+				.value_parser(["lint", "lookup", "explain"])
+				.help("'lint' looks for invalid and shadowed entries;\n'lookup' shows which entry matches given model and firmware;\n'explain' also shows attribute descriptions that such drive would get")
+			)
+			.arg(Arg::new("model")
+				.help("drive model, as reported by the drive (see `hdd <device> info`); for SCSI drives, that's vendor and product identification separated by a space")
+			)
+			.arg(Arg::new("firmware")
+				.help("drive firmware revision")
+			)
+			.arg(Arg::new("interface")
+				.long("interface")
+				.num_args(1)
+				.value_parser(["ata", "scsi", "nvme"])
+				.default_value("ata")
+				.help("which kind of drivedb entries to look through")
+			)
+			.arg(Arg::new("drivetype")
+				.long("type")
+				.num_args(1)
+				.value_parser(["hdd", "ssd"])
+				.help("type of the drive, to filter out type-specific attribute descriptions")
+			)
+			.arg(Arg::new("vendorattribute")
+				.action(clap::ArgAction::Append)
+				.short('v') // smartctl-like
+				.long("vendorattribute") // smartctl-like
+				.num_args(1)
+				.value_name("id,format[:byteorder][,name]")
+				.help("set display option for vendor attribute 'id'")
+			)
+			.arg(arg_drivedb())
+			.arg(arg_drivedb_cache())
+			.arg(arg_json())
+	}
+
+	fn run(
+		&self,
+		_: &Option<&Path>,
+		dev: &Option<&DeviceArgument>,
+		args: &ArgMatches,
+	) {
+		if dev.is_some() {
+			// TODO show usage and whatnot
+			eprint!("<device> is redundant\n");
+			::std::process::exit(1);
+		};
+
+		let db = open_drivedb(args.get_many::<String>("drivedb")
+			.map(|vals| vals.map(|v| v.to_string()).collect()),
+			args.get_one::<String>("drivedb-cache"));
+
+		let use_json = args.get_flag("json");
+
+		// unwrap: this argument is required
+		match args.get_one::<String>("action").unwrap().as_str() {
+			"lint" => lint(&db, use_json),
+			"lookup" => lookup(&db, args, false, use_json),
+			"explain" => lookup(&db, args, true, use_json),
+			_ => unreachable!(),
+		}
+	}
+}
@@ -36,6 +36,10 @@ impl Subcommand for Health {
 			#[cfg(not(target_os = "linux"))]
 			DeviceArgument::ATA(_, id) => id,
 			DeviceArgument::SAT(_, id) => id,
+			DeviceArgument::ATAPI(_, _) => {
+				eprint!("S.M.A.R.T. is not available for ATAPI devices\n");
+				::std::process::exit(1);
+			},
 			DeviceArgument::SCSI(_) => unimplemented!(),
 		};
 
@@ -46,6 +50,7 @@ impl Subcommand for Health {
 				#[cfg(not(target_os = "linux"))]
 				DeviceArgument::ATA(dev, _) => dev.get_smart_health().unwrap(),
 				DeviceArgument::SAT(dev, _) => dev.get_smart_health().unwrap(),
+				DeviceArgument::ATAPI(_, _) => unreachable!(),
 				DeviceArgument::SCSI(_) => unimplemented!(),
 			};
 
@@ -1,4 +1,5 @@
-use hdd::ata::data::id;
+use hdd::ata::data::{id, id_packet, id_log, capacity};
+use hdd::ata::misc::Misc;
 use hdd::drivedb;
 use hdd::scsi::SCSICommon;
 use hdd::scsi::data::inquiry;
@@ -14,7 +15,7 @@ use separator::Separatable;
 use number_prefix::NumberPrefix;
 
 use crate::{DeviceArgument, open_drivedb};
-use super::{Subcommand, arg_json, arg_drivedb};
+use super::{Subcommand, arg_json, arg_drivedb, arg_drivedb_cache};
 
 use std::path::Path;
 
@@ -31,55 +32,125 @@ fn ternary_feature_status(x: &id::Ternary) -> &'static str {
 	}
 }
 
-fn ata_security_status(state: u16, master_password_id: u16) -> String {
-	if (state & 0x0001) == 0 {
-		return "Unavailable".to_string();
-	}
+pub fn ata_security_status(status: &Option<id::SecurityStatus>) -> String {
+	let status = match status {
+		Some(status) => status,
+		None => return "Unavailable".to_string(),
+	};
 
 	let mut out = String::new();
-	if (state & 0x0002) == 0 {
+	if !status.enabled {
 		out.push_str("Disabled, ");
-		if (state & 0x0008) == 0 {
+		if !status.frozen {
 			out.push_str("NOT FROZEN [SEC1]");
 		} else {
 			out.push_str("frozen [SEC2]");
 		}
 	} else {
 		out.push_str("ENABLED, PW level ");
-		if (state & 0x0100) == 0 {
-			out.push_str("HIGH");
-		} else {
-			out.push_str("MAX");
-		}
+		out.push_str(match status.level {
+			id::SecurityLevel::High => "HIGH",
+			id::SecurityLevel::Maximum => "MAX",
+		});
 
-		if (state & 0x0004) == 0 {
+		if !status.locked {
 			out.push_str(", not locked, ");
-			if (state & 0x0008) == 0 {
+			if !status.frozen {
 				out.push_str("not frozen [SEC5]");
 			} else {
 				out.push_str("frozen [SEC6]");
 			}
 		} else {
 			out.push_str(", **LOCKED** [SEC4]");
-			if (state & 0x0010) != 0 {
+			if status.count_expired {
 				out.push_str(", PW ATTEMPTS EXCEEDED");
 			}
 		}
 	}
 
-	if 0x0000 < master_password_id && master_password_id < 0xfffe {
-		out.push_str(&format!(", Master PW ID: 0x{:04x}", master_password_id));
+	match status.master_password_id {
+		// 0xfffe is the factory default, not worth showing
+		Some(id) if id != 0xfffe => out.push_str(&format!(", Master PW ID: 0x{:04x}", id)),
+		_ => (),
 	}
 
 	out
 }
 
+fn apm_level(level: u8) -> String {
+	format!("{} ({})", level, match level {
+		0x01 => "minimum power consumption with standby",
+		0x02 ..= 0x7f => "intermediate level with standby",
+		0x80 => "minimum power consumption without standby",
+		0x81 ..= 0xfd => "intermediate level without standby",
+		0xfe => "maximum performance",
+		_ => "reserved",
+	})
+}
+
+fn aam_level(level: u8) -> String {
+	format!("{} ({})", level, match level {
+		0x00 => "vendor specific",
+		0x01 ..= 0x7f => "unknown/retired",
+		0x80 => "quietest",
+		0x81 ..= 0xfd => "intermediate",
+		0xfe => "maximum performance",
+		_ => "reserved",
+	})
+}
+
 fn print_kv(label: &str, value: impl ::std::fmt::Display) {
 	print!("{:<28}{}\n", label, value);
 }
 
-fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
+fn print_meta(meta: &Option<drivedb::DriveMeta>) {
+	if let Some(meta) = meta {
+		if let Some(family) = &meta.family {
+			print!("Model family according to drive database:\n  {}\n", family);
+		} else {
+			print!("This drive is not in the drive database\n");
+		}
+		for entry in meta.default.iter().chain(meta.entry.iter()) {
+			for preset in entry.invalid_presets.iter() {
+				eprint!("drivedb entry at {} has invalid preset, ignoring: {}\n", entry, preset);
+			}
+		}
+		if let Some(warning) = &meta.warning {
+			print!("\n══════ WARNING ══════\n{}\n═════════════════════\n", warning);
+		}
+	}
+}
+
+// family, warning and where they came from, for JSON output
+fn insert_meta_json(info: &mut serde_json::Value, meta: &Option<drivedb::DriveMeta>) {
+	if let Some(meta) = meta {
+		if let Some(family) = &meta.family {
+			info.as_object_mut().unwrap().insert(
+				"family".to_string(),
+				serde_json::to_value(family).unwrap(),
+			);
+		}
+		if let Some(warning) = &meta.warning {
+			info.as_object_mut().unwrap().insert(
+				"warning".to_string(),
+				serde_json::to_value(warning).unwrap(),
+			);
+		}
+		// where the data above came from, for the sake of debugging drivedb lookups
+		info.as_object_mut().unwrap().insert(
+			"drivedb_entry".to_string(),
+			serde_json::to_value(&meta.entry).unwrap(),
+		);
+		info.as_object_mut().unwrap().insert(
+			"drivedb_default".to_string(),
+			serde_json::to_value(&meta.default).unwrap(),
+		);
+	}
+}
+
+fn print_ata_id(id: &id::Id, id_log: &Option<id_log::DeviceData>, capacity: &capacity::Capacity, meta: &Option<drivedb::DriveMeta>) {
 	if id.incomplete { print!("WARNING: device reports information it provides is incomplete\n\n"); }
+	if id.checksum_valid == Some(false) { print!("WARNING: IDENTIFY DEVICE checksum is invalid, information below might be corrupted\n\n"); }
 
 	// XXX id.is_ata is deemed redundant and is skipped
 	// XXX we're skipping id.commands_supported for now as it is hardly of any interest to users
@@ -90,20 +161,17 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 		id::RPM::NonRotating => print_kv("RPM:", "N/A (SSD or other non-rotating media)"),
 		id::RPM::RPM(i) => print_kv("RPM:", format!("{}", i)),
 	};
+	if let Some(form_factor) = id.form_factor {
+		print_kv("Form factor:", form_factor);
+	}
 	print_kv("Firmware:", &id.firmware);
 	print_kv("Serial:", &id.serial);
+	if let Some(media_serial) = &id.media_serial {
+		print_kv("Media serial:", media_serial);
+	}
 	// TODO: id.wwn_supported is cool, but actual WWN ID is better
 
-	if let Some(meta) = meta {
-		if let Some(family) = meta.family {
-			print!("Model family according to drive database:\n  {}\n", family);
-		} else {
-			print!("This drive is not in the drive database\n");
-		}
-		if let Some(warning) = meta.warning {
-			print!("\n══════ WARNING ══════\n{}\n═════════════════════\n", warning);
-		}
-	}
+	print_meta(meta);
 
 	print!("\n");
 
@@ -126,6 +194,9 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 		"Sector size (logical/physical):",
 		format!("{}/{}", id.sector_size_log, id.sector_size_phy),
 	);
+	if let Some(zoned) = id.zoned {
+		print_kv("Zoned device:", zoned);
+	}
 
 	print!("\n");
 
@@ -140,6 +211,28 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 		}
 		print_kv("SATA Version:", details);
 	}
+	if let Some(sata) = &id.sata_capabilities {
+		if !sata.link_speeds.is_empty() {
+			print_kv("SATA link speeds:", sata.link_speeds.join(", "));
+		}
+		print_kv("NCQ:", match id.queue_depth {
+			Some(depth) if sata.ncq => format!("supported, queue depth {}", depth),
+			_ => "not supported".to_string(),
+		});
+
+		let mut features = vec![];
+		if sata.ncq_priority { features.push("NCQ priority"); }
+		if sata.ncq_streaming { features.push("NCQ streaming"); }
+		if sata.ncq_management { features.push("NCQ management"); }
+		if sata.ncq_send_receive { features.push("NCQ send/receive"); }
+		if sata.ncq_autosense { features.push("NCQ autosense"); }
+		if sata.phy_event_counters { features.push("Phy event counters"); }
+		if sata.read_log_dma_ext { features.push("READ LOG DMA EXT"); }
+		if sata.unload_while_ncq_outstanding { features.push("unload while NCQ outstanding"); }
+		if !features.is_empty() {
+			print_kv("SATA features:", features.join(", "));
+		}
+	}
 	print_kv(
 		"TRIM Command:",
 		if !id.trim_supported {
@@ -160,10 +253,43 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 
 	// The following guide, when printed, is exactly 80 characters
 	// ... "..............................................................supported disabled\n"
-	print_kv("Host protected area:", format!("{}", id.hpa));
+	print_kv("Host protected area:", match (id.hpa, capacity.native) {
+		(id::Ternary::Unsupported, _) => "Unavailable".to_string(),
+		// ACS-3 and later replace SET MAX ADDRESS with the Accessible Max Address feature set, see below
+		_ if id.ama_supported => "Superseded by Accessible Max Address".to_string(),
+		(_, Some(native)) if capacity.is_clipped() =>
+			format!("ACTIVE, {} of {} sectors are hidden", native - capacity.accessible, native),
+		(_, Some(_)) => "Inactive".to_string(),
+		(_, None) => "Unknown (cannot read native max address)".to_string(),
+	});
+	if id.ama_supported {
+		print_kv("Accessible Max Address:", match capacity.native {
+			Some(native) if capacity.is_clipped() =>
+				format!("ACTIVE, {} of {} sectors are hidden", native - capacity.accessible, native),
+			Some(_) => "Inactive".to_string(),
+			None => "Unknown (cannot get native max address)".to_string(),
+		});
+	}
+	if id.dco_supported {
+		print_kv("Device config. overlay:", match capacity.dco {
+			Some(dco) if capacity.is_dco_restricted() =>
+				format!("ACTIVE, native capacity is restricted to {} of {} sectors", capacity.native.unwrap_or(capacity.accessible), dco),
+			Some(_) => "Inactive".to_string(),
+			None => "Unknown (DEVICE CONFIGURATION IDENTIFY failed)".to_string(),
+		});
+	}
 	print_kv("SMART support is:", ternary_feature_status(&id.smart));
-	print_kv("AAM feature is:", ternary_feature_status(&id.aam));
-	print_kv("APM feature is:", ternary_feature_status(&id.apm));
+	match id.aam_level {
+		Some(level) => print_kv("AAM level is:", format!("{}{}",
+			aam_level(level),
+			id.aam_recommended_level.map(|r| format!(", recommended: {}", aam_level(r))).unwrap_or_default(),
+		)),
+		None => print_kv("AAM feature is:", ternary_feature_status(&id.aam)),
+	}
+	match id.apm_level {
+		Some(level) => print_kv("APM level is:", apm_level(level)),
+		None => print_kv("APM feature is:", ternary_feature_status(&id.apm)),
+	}
 	print_kv("Rd look-ahead is:", ternary_feature_status(&id.read_look_ahead));
 	print_kv("Write cache is:", ternary_feature_status(&id.write_cache));
 	print_kv(
@@ -174,7 +300,8 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 			"Unavailable"
 		},
 	);
-	print_kv("ATA Security is:", ata_security_status(id.security_state, id.security_master_pw_id));
+	print_kv("Sense Data Reporting:", ternary_feature_status(&id.sense_data_reporting));
+	print_kv("ATA Security is:", ata_security_status(&id.security_status));
 	print_kv(
 		"Wt Cache Reorder:",
 		if id.sct_feature_control_supported {
@@ -183,7 +310,65 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 			"Unavailable"
 		},
 	);
+	if let Some(sata) = &id.sata_capabilities {
+		print_kv("Non-zero buffer offsets:", ternary_feature_status(&sata.non_zero_buffer_offsets));
+		print_kv("DMA setup auto-activate:", ternary_feature_status(&sata.dma_setup_auto_activation));
+		print_kv("In-order data delivery:", ternary_feature_status(&sata.in_order_data_delivery));
+		print_kv("SW settings preservation:", ternary_feature_status(&sata.software_settings_preservation));
+	}
 	print_kv("Power management:", bool_to_sup(id.power_mgmt_supported));
+	if let Some(sata) = &id.sata_capabilities {
+		print_kv("Host-initiated link PM:", bool_to_sup(sata.hipm));
+		print_kv("Device-initiated link PM:", ternary_feature_status(&sata.dipm));
+		print_kv(
+			"Auto Partial to Slumber:",
+			format!("host {}, device {}",
+				if sata.host_automatic_partial_to_slumber { "supported" } else { "not supported" },
+				ternary_feature_status(&sata.device_automatic_partial_to_slumber).to_lowercase(),
+			),
+		);
+		let devsleep_timing = id_log.as_ref()
+			.and_then(|log| log.sata.as_ref())
+			.and_then(|sata| sata.devsleep_timing);
+		print_kv("Device Sleep:", match (sata.devsleep, devsleep_timing) {
+			(id::Ternary::Unsupported, _) => ternary_feature_status(&sata.devsleep).to_string(),
+			(devsleep, Some(timing)) => format!("{}, exit timeout {} ms, min. assertion time {} ms",
+				ternary_feature_status(&devsleep),
+				timing.exit_timeout,
+				timing.min_assertion_time,
+			),
+			(devsleep, None) => ternary_feature_status(&devsleep).to_string(),
+		});
+	}
+	print_kv(
+		"Standby timer values:",
+		format!("{}{}",
+			if id.standby_timer_vendor_specific { "vendor specific" } else { "standard" },
+			if id.standby_timer_has_minimum { ", with device-specific minimum" } else { "" },
+		),
+	);
+	if let Some(config) = id.specific_configuration {
+		print_kv("Spin-up after power-up:", if config.spin_up_required { "requires SET FEATURES" } else { "automatic" });
+	}
+	print_kv(
+		"Read/Write Multiple:",
+		match (id.multiple_sectors_max, id.multiple_sectors_current) {
+			(None, _) => "not supported".to_string(),
+			(Some(max), Some(current)) => format!("up to {} sectors, current: {}", max, current),
+			(Some(max), None) => format!("up to {} sectors", max),
+		},
+	);
+	print_kv(
+		"IORDY:",
+		match (id.iordy_supported, id.iordy_can_be_disabled) {
+			(false, _) => "not supported",
+			(true, false) => "supported",
+			(true, true) => "supported, can be disabled",
+		},
+	);
+	if id.cfa {
+		print_kv("CompactFlash:", "supported");
+	}
 	print_kv("General purpose logging:", bool_to_sup(id.gp_logging_supported));
 	print_kv("Trusted computing:", bool_to_sup(id.trusted_computing_supported));
 
@@ -195,11 +380,35 @@ fn print_ata_id(id: &id::Id, meta: &Option<drivedb::DriveMeta>) {
 	print!("\n");
 }
 
-fn print_scsi_id(inquiry: &inquiry::Inquiry) {
+fn print_atapi_id(id: &id_packet::PacketId) {
+	if id.incomplete { print!("WARNING: device reports information it provides is incomplete\n\n"); }
+
+	print_kv("Model:", &id.model);
+	print_kv("Firmware:", &id.firmware);
+	print_kv("Serial:", &id.serial);
+	print_kv("Device type:", format!("ATAPI {}", id.device_type));
+	print_kv("Removable media:", if id.removable { "yes" } else { "no" });
+	if let Some(size) = id.command_packet_size {
+		print_kv("Command packet size:", format!("{} bytes", size));
+	}
+
+	print!("\n");
+
+	print_kv("DMA:", bool_to_sup(id.dma_supported));
+	print_kv("Power management:", bool_to_sup(id.power_mgmt_supported));
+	print_kv("Rd look-ahead is:", if id.read_look_ahead { "Enabled" } else { "Disabled" });
+	print_kv("Write cache is:", if id.write_cache { "Enabled" } else { "Disabled" });
+
+	print!("\n");
+}
+
+fn print_scsi_id(inquiry: &inquiry::Inquiry, meta: &Option<drivedb::DriveMeta>) {
 	print!("Vendor:   {}\n", inquiry.vendor_id);
 	print!("Model:    {}\n", inquiry.product_id);
 	print!("Firmware: {}\n", inquiry.product_rev);
 
+	print_meta(meta);
+
 	// TODO other inquiry fields, capacity, …
 }
 
@@ -210,6 +419,7 @@ impl Subcommand for Info {
 			.about("Prints a basic information about the device")
 			.arg(arg_json())
 			.arg(arg_drivedb())
+			.arg(arg_drivedb_cache())
 	}
 
 	fn run(
@@ -224,11 +434,11 @@ impl Subcommand for Info {
 			::std::process::exit(1);
 		});
 
-		let ata_id = match dev {
+		let ata: Option<(&dyn Misc, _)> = match dev {
 			#[cfg(not(target_os = "linux"))]
-			DeviceArgument::ATA(_, id) => Some(id),
-			DeviceArgument::SAT(_, id) => Some(id),
-			DeviceArgument::SCSI(_) => None,
+			DeviceArgument::ATA(dev, id) => Some((dev, id)),
+			DeviceArgument::SAT(dev, id) => Some((dev, id)),
+			DeviceArgument::ATAPI(_, _) | DeviceArgument::SCSI(_) => None,
 		};
 
 		let use_json = args.get_flag("json");
@@ -237,45 +447,79 @@ impl Subcommand for Info {
 			let (_sense, data) = dev.scsi_inquiry(false, 0).unwrap();
 			let inquiry = inquiry::parse_inquiry(&data);
 
+			let drivedb = open_drivedb(args.get_many::<String>("drivedb")
+				.map(|vals| vals.map(|v| v.to_string()).collect()),
+				args.get_one::<String>("drivedb-cache"));
+			let meta = Some(drivedb.render_scsi_meta(&inquiry));
+
 			if use_json {
-				let info = serde_json::to_value(&inquiry).unwrap();
+				let mut info = serde_json::to_value(&inquiry).unwrap();
+				insert_meta_json(&mut info, &meta);
 				print!("{}\n", serde_json::to_string(&info).unwrap());
 			} else {
-				print_scsi_id(&inquiry);
+				print_scsi_id(&inquiry, &meta);
+			}
+		}
+
+		if let DeviceArgument::ATAPI(_, id) = dev {
+			if use_json {
+				print!("{}\n", serde_json::to_string(&id).unwrap());
+			} else {
+				print_atapi_id(id);
 			}
 		}
 
-		if let Some(id) = ata_id {
+		if let Some((dev, id)) = ata {
+			// Identify Device Data log is more precise than legacy IDENTIFY DEVICE words, so use it wherever possible
+			let id_log = if id.gp_logging_supported {
+				dev.get_device_data_log().ok()
+			} else { None };
+			let mut id = id.clone();
+			if let Some(id_log) = &id_log {
+				id_log.update_id(&mut id);
+			}
+
+			let capacity = dev.get_capacity(&id);
+
 			let drivedb = open_drivedb(args.get_many::<String>("drivedb")
-				.map(|vals| vals.map(|v| v.to_string()).collect()));
-			let meta = drivedb.as_ref().map(|drivedb| drivedb.render_meta(
+				.map(|vals| vals.map(|v| v.to_string()).collect()),
+				args.get_one::<String>("drivedb-cache"));
+			let meta = Some(drivedb.render_meta(
 				&id,
 				// no need to parse custom vendor attributes,
 				// we're only using drivedb for the family and the warning here
 				&vec![],
 			));
 
+			// drivedb entries for the devices with byte-swapped strings match these swapped strings, and only then tell us that strings need fixing
+			if meta.as_ref().map(|meta| meta.has_firmware_bug(drivedb::FirmwareBug::SwapId)).unwrap_or(false)
+				&& let Ok(fixed) = dev.get_device_id_fixed(&meta)
+			{
+				id = fixed;
+				if let Some(id_log) = &id_log {
+					id_log.update_id(&mut id);
+				}
+			}
+
 			if use_json {
 				let mut info = serde_json::to_value(&id).unwrap();
 
-				if let Some(meta) = &meta {
-					if let Some(family) = meta.family {
-						info.as_object_mut().unwrap().insert(
-							"family".to_string(),
-							serde_json::to_value(family).unwrap(),
-						);
-					}
-					if let Some(warning) = meta.warning {
-						info.as_object_mut().unwrap().insert(
-							"warning".to_string(),
-							serde_json::to_value(warning).unwrap(),
-						);
-					}
+				info.as_object_mut().unwrap().insert(
+					"capacity_details".to_string(),
+					serde_json::to_value(&capacity).unwrap(),
+				);
+				if let Some(id_log) = &id_log {
+					info.as_object_mut().unwrap().insert(
+						"device_data_log".to_string(),
+						serde_json::to_value(id_log).unwrap(),
+					);
 				}
 
+				insert_meta_json(&mut info, &meta);
+
 				print!("{}\n", serde_json::to_string(&info).unwrap());
 			} else {
-				print_ata_id(&id, &meta);
+				print_ata_id(&id, &id_log, &capacity, &meta);
 			}
 		}
 	}
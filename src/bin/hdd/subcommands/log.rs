@@ -0,0 +1,130 @@
+use hdd::ata::misc::Misc;
+use hdd::ata::data::{id, pending_defects, ncq_error};
+use hdd::scsi::data::sense::key::{SenseKey, decode_asc};
+
+use clap::{
+	Arg,
+	ArgMatches,
+	Command,
+};
+
+use serde_json;
+
+use crate::DeviceArgument;
+use super::{Subcommand, arg_json};
+
+use std::path::Path;
+
+fn print_pending_defects(defects: &[pending_defects::PendingDefect]) {
+	if defects.is_empty() {
+		print!("No pending defects\n");
+		return;
+	}
+
+	print!("{} pending defect(s):\n", defects.len());
+	print!("{:>20} {:>16}\n", "LBA", "Power-on hours");
+	for defect in defects {
+		print!("{:>20} {:>16}\n", defect.lba, match defect.power_on_hours {
+			Some(hours) => hours.to_string(),
+			None => "-".to_string(),
+		});
+	}
+}
+
+fn print_ncq_error(error: &ncq_error::NCQError) {
+	if !error.checksum_valid {
+		print!("WARNING: log checksum is invalid, information below might be corrupted\n\n");
+	}
+	if !error.is_error() {
+		print!("No NCQ errors\n");
+		return;
+	}
+
+	match error.tag {
+		Some(tag) => print!("Failed queued command, tag {}\n", tag),
+		None => print!("Failed non-queued command\n"),
+	}
+	print!("Status: 0x{:02x}, error: 0x{:02x}\n", error.status, error.error);
+	print!("LBA: {}, sector count: {}\n", error.lba, error.count);
+	if error.sense_key != 0 || error.asc != 0 || error.ascq != 0 {
+		print!("Sense: {:?} ({})\n",
+			SenseKey::from(error.sense_key),
+			decode_asc(error.asc, error.ascq)
+				.map(|x| x.to_string())
+				.unwrap_or_else(|| format!("unknown additional sense code: {:02x} {:02x}", error.asc, error.ascq)),
+		);
+	}
+}
+
+fn run(dev: &dyn Misc, id: &id::Id, name: &str, use_json: bool) -> Result<(), String> {
+	if !id.gp_logging_supported {
+		return Err("General Purpose Logging is not supported".to_string());
+	}
+
+	match name {
+		"pending-defects" => {
+			let defects = dev.get_pending_defects().map_err(|e| e.to_string())?;
+			if use_json {
+				print!("{}\n", serde_json::to_string(&defects).unwrap());
+			} else {
+				print_pending_defects(&defects);
+			}
+		},
+		"ncq-error" => {
+			let error = dev.get_ncq_error().map_err(|e| e.to_string())?;
+			if use_json {
+				print!("{}\n", serde_json::to_string(&error).unwrap());
+			} else {
+				print_ncq_error(&error);
+			}
+		},
+		_ => unreachable!(),
+	}
+
+	Ok(())
+}
+
+pub struct Log {}
+impl Subcommand for Log {
+	fn subcommand(&self) -> Command {
+		Command::new("log")
+			.about("Prints contents of the device logs")
+			.arg(Arg::new("name")
+				.required(true)
+				.value_parser(["pending-defects", "ncq-error"])
+				.help("'pending-defects' lists LBAs that device failed to read and is going to reallocate;\n'ncq-error' describes the last failed queued command")
+			)
+			.arg(arg_json())
+	}
+
+	fn run(
+		&self,
+		_: &Option<&Path>,
+		dev: &Option<&DeviceArgument>,
+		args: &ArgMatches,
+	) {
+		let dev = dev.unwrap_or_else(|| {
+			// TODO show usage and whatnot
+			eprint!("<device> is required\n");
+			::std::process::exit(1);
+		});
+
+		let (dev, id): (&dyn Misc, _) = match dev {
+			#[cfg(not(target_os = "linux"))]
+			DeviceArgument::ATA(dev, id) => (dev, id),
+			DeviceArgument::SAT(dev, id) => (dev, id),
+			DeviceArgument::ATAPI(_, _) | DeviceArgument::SCSI(_) => {
+				eprint!("These logs are only available for ATA devices\n");
+				::std::process::exit(1);
+			},
+		};
+
+		// unwrap: this argument is required
+		let name = args.get_one::<String>("name").unwrap().as_str();
+
+		if let Err(e) = run(dev, id, name, args.get_flag("json")) {
+			eprint!("Cannot read {} log: {}\n", name, e);
+			::std::process::exit(1);
+		}
+	}
+}
@@ -2,6 +2,11 @@ mod info;
 mod health;
 mod attrs;
 mod list;
+mod set;
+mod power;
+mod security;
+mod log;
+mod drivedb;
 
 use std::collections::HashMap;
 use clap::{self, Arg, ArgAction, ArgMatches, Command};
@@ -29,7 +34,15 @@ pub fn arg_drivedb() -> Arg {
 					drivedb_additional.iter().map(|i| format!("+{}", i)).collect::<Vec<_>>().join("\n"),
 				)
 			*/
-			.help("paths to drivedb files to look for\nuse 'FILE' for main (system-wide) file, '+FILE' for additional entries\nentries are looked up in every additional file in order of their appearance, then in the first valid main file, stopping at the first match\n(this option and its behavior is, to some extent, consistent with '-B' from smartctl)")
+			.help("paths to drivedb files to look for\nuse 'FILE' for main (system-wide) file, '+FILE' for additional entries\nentries are looked up in every additional file in order of their appearance, then in the first valid main file, stopping at the first match\nif no main file can be loaded, built-in snapshot is used, if hdd was built with one\n(this option and its behavior is, to some extent, consistent with '-B' from smartctl)")
+}
+
+pub fn arg_drivedb_cache() -> Arg {
+	Arg::new("drivedb-cache")
+			.long("drivedb-cache")
+			.num_args(1)
+			.value_name("DIR")
+			.help("keep parsed drivedb files in DIR, so that subsequent runs don't have to parse them again")
 }
 
 pub trait Subcommand: Sync {
@@ -41,6 +54,11 @@ static HEALTH: health::Health = health::Health {};
 static LIST: list::List = list::List {};
 static INFO: info::Info = info::Info {};
 static ATTRS: attrs::Attrs = attrs::Attrs {};
+static SET: set::Set = set::Set {};
+static POWER: power::Power = power::Power {};
+static SECURITY: security::Security = security::Security {};
+static LOG: log::Log = log::Log {};
+static DRIVEDB: drivedb::DriveDB = drivedb::DriveDB {};
 
 lazy_static! {
 	pub static ref SUBCOMMANDS: HashMap<&'static str, &'static dyn Subcommand> = {
@@ -49,6 +67,11 @@ lazy_static! {
 		m.insert("list",   &LIST);
 		m.insert("info",   &INFO);
 		m.insert("attrs",  &ATTRS);
+		m.insert("set",    &SET);
+		m.insert("power",  &POWER);
+		m.insert("security", &SECURITY);
+		m.insert("log",    &LOG);
+		m.insert("drivedb", &DRIVEDB);
 		m
 	};
 }
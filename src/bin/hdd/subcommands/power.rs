@@ -0,0 +1,109 @@
+use hdd::ata::misc::Misc;
+use hdd::ata::data::power;
+use hdd::scsi::{SCSICommon, PowerCondition};
+
+use clap::{
+	Arg,
+	ArgMatches,
+	Command,
+};
+
+use crate::DeviceArgument;
+use super::Subcommand;
+
+use std::path::Path;
+
+fn ata_power(dev: &dyn Misc, mode: &str, timer: Option<u8>) -> Result<(), String> {
+	match (mode, timer) {
+		("standby", None) => dev.standby_immediate(),
+		("standby", Some(timer)) => dev.standby(timer),
+		("idle", None) => dev.idle_immediate(),
+		("idle", Some(timer)) => dev.idle(timer),
+		// any media access will do that for us
+		("active", _) => return Err("ATA devices have no explicit command to become active".to_string()),
+		_ => unreachable!(),
+	}.map_err(|e| e.to_string())
+}
+
+fn scsi_power<T: SCSICommon>(dev: &T, mode: &str, timer: Option<u8>) -> Result<(), String> {
+	if timer.is_some() {
+		// that would require MODE SELECT with Power Condition mode page
+		return Err("setting timers for SCSI devices is not supported".to_string());
+	}
+
+	dev.start_stop_unit(false, match mode {
+		"standby" => PowerCondition::ForceStandby0,
+		"idle" => PowerCondition::ForceIdle0,
+		"active" => PowerCondition::Active,
+		_ => unreachable!(),
+	}, false)
+		.map(|_| ())
+		.map_err(|e| e.to_string())
+}
+
+pub struct Power {}
+impl Subcommand for Power {
+	fn subcommand(&self) -> Command {
+		Command::new("power")
+			.about("Switches device into a given power mode")
+			.arg(Arg::new("mode")
+				.required(true)
+				.value_parser(["standby", "idle", "active"])
+				.help("power mode to enter: 'standby' spins the device down, 'idle' keeps it spinning; 'active' only works for SCSI devices")
+			)
+			.arg(Arg::new("timer")
+				.long("timer")
+				.num_args(1)
+				.value_name("SECONDS")
+				.value_parser(clap::value_parser!(u32))
+				.help("also set standby timer (ATA only), rounded up to what the device supports: up to 20 minutes in 5-second steps, then up to 5.5 hours in 30-minute steps; 0 disables the timer")
+			)
+	}
+
+	fn run(
+		&self,
+		_: &Option<&Path>,
+		dev: &Option<&DeviceArgument>,
+		args: &ArgMatches,
+	) {
+		let dev = dev.unwrap_or_else(|| {
+			// TODO show usage and whatnot
+			eprint!("<device> is required\n");
+			::std::process::exit(1);
+		});
+
+		// unwrap: this argument is required
+		let mode = args.get_one::<String>("mode").unwrap().as_str();
+
+		let timer = args.get_one::<u32>("timer").map(|&seconds| {
+			power::encode_standby_timer(seconds).unwrap_or_else(|| {
+				eprint!("Standby timer value is too large\n");
+				::std::process::exit(1);
+			})
+		});
+
+		let result = match dev {
+			#[cfg(not(target_os = "linux"))]
+			DeviceArgument::ATA(dev, _) => ata_power(dev, mode, timer),
+			DeviceArgument::SAT(dev, _) => ata_power(dev, mode, timer),
+			DeviceArgument::ATAPI(dev, _) => ata_power(dev, mode, timer),
+			DeviceArgument::SCSI(dev) => scsi_power(dev, mode, timer),
+		};
+
+		match result {
+			Ok(()) => {
+				print!("Power mode: {}\n", mode);
+				if let Some(timer) = timer {
+					match power::decode_standby_timer(timer) {
+						Some(seconds) => print!("Standby timer: {} s\n", seconds),
+						None => print!("Standby timer: disabled\n"),
+					}
+				}
+			},
+			Err(e) => {
+				eprint!("Cannot switch to {} mode: {}\n", mode, e);
+				::std::process::exit(1);
+			},
+		}
+	}
+}
@@ -0,0 +1,189 @@
+use hdd::ata::misc::Misc;
+use hdd::ata::security::{self, Security as _, Password};
+use hdd::ata::data::id;
+
+use clap::{
+	Arg,
+	ArgAction,
+	ArgMatches,
+	Command,
+};
+
+use crate::DeviceArgument;
+use super::Subcommand;
+use super::info::ata_security_status;
+
+use std::path::Path;
+
+fn minutes(time: Option<id::EraseTime>) -> String {
+	match time {
+		Some(id::EraseTime::Minutes(time)) => format!("{} min", time),
+		Some(id::EraseTime::MoreThan(time)) => format!("more than {} min", time),
+		None => "unknown".to_string(),
+	}
+}
+
+fn print_status(status: &Option<id::SecurityStatus>) {
+	print!("ATA Security is: {}\n", ata_security_status(status));
+	if let Some(status) = status {
+		print!("Security erase time: {}\n", minutes(status.erase_time));
+		if status.enhanced_erase_supported {
+			print!("Enhanced security erase time: {}\n", minutes(status.enhanced_erase_time));
+		} else {
+			print!("Enhanced security erase: not supported\n");
+		}
+	}
+}
+
+// commands that change anything require security to be supported and not frozen, and the operator to confirm that they've got the right device
+fn check_destructive(id: &id::Id, args: &ArgMatches) -> Result<id::SecurityStatus, String> {
+	match args.get_one::<String>("confirm") {
+		Some(serial) if serial == &id.serial => (),
+		Some(_) => return Err(format!("--confirm value does not match device serial number ({})", id.serial)),
+		None => return Err(format!("this command might render device unusable or destroy all the data on it; add --confirm {} if you're sure", id.serial)),
+	}
+
+	let status = id.security_status.clone()
+		.ok_or("Security feature set is not supported")?;
+
+	if status.frozen {
+		return Err("security settings are frozen (usually by BIOS or OS); power cycle the device (e.g. hot-plug it) and try again".to_string());
+	}
+	if status.count_expired {
+		return Err("password attempts exceeded; power cycle the device and try again".to_string());
+	}
+
+	Ok(status)
+}
+
+fn get_password(args: &ArgMatches) -> Result<Password, String> {
+	let password = args.get_one::<String>("password")
+		.ok_or("--password is required")?;
+
+	let password = if args.get_flag("master") {
+		Password::master(password.as_bytes())
+	} else {
+		Password::user(password.as_bytes())
+	};
+
+	password.ok_or("password should not be longer than 32 bytes".to_string())
+}
+
+fn run(dev: &dyn Misc, id: &id::Id, action: &str, args: &ArgMatches) -> Result<(), String> {
+	match action {
+		"status" => print_status(&id.security_status),
+		"freeze" => {
+			dev.security_freeze_lock().map_err(|e| e.to_string())?;
+			print!("Security settings are frozen until the next power cycle\n");
+		},
+		"set-password" => {
+			let status = check_destructive(id, args)?;
+			// locked device would abort it anyway; erase, on the other hand, is exactly how one deals with locked devices
+			if status.locked {
+				return Err("device is locked".to_string());
+			}
+			let password = get_password(args)?;
+			let level = match args.get_one::<String>("level").map(|s| s.as_str()) {
+				Some("maximum") => id::SecurityLevel::Maximum,
+				_ => id::SecurityLevel::High,
+			};
+
+			dev.security_set_password(&password, level, None).map_err(|e| e.to_string())?;
+			match password.identifier {
+				security::Identifier::User => print!("User password is set; device will be LOCKED after the next power cycle\n"),
+				security::Identifier::Master => print!("Master password is set\n"),
+			}
+		},
+		"erase" => {
+			let status = check_destructive(id, args)?;
+			let password = get_password(args)?;
+			let enhanced = args.get_flag("enhanced");
+
+			if !status.enabled {
+				return Err("user password is not set; use 'set-password' first".to_string());
+			}
+			if enhanced && !status.enhanced_erase_supported {
+				return Err("enhanced security erase is not supported".to_string());
+			}
+
+			print!("Erasing, estimated time: {}\n", minutes(if enhanced { status.enhanced_erase_time } else { status.erase_time }));
+			dev.security_erase(&password, enhanced, security::erase_timeout(&status, enhanced)).map_err(|e| e.to_string())?;
+			print!("Device is erased\n");
+		},
+		_ => unreachable!(),
+	}
+
+	Ok(())
+}
+
+pub struct Security {}
+impl Subcommand for Security {
+	fn subcommand(&self) -> Command {
+		Command::new("security")
+			.about("Manages ATA Security feature set: freezes security settings, sets password, erases the device")
+			.arg(Arg::new("action")
+				.required(true)
+				.value_parser(["status", "freeze", "set-password", "erase"])
+				.help("'status' shows security state, 'freeze' prevents changes until the next power cycle;\n'set-password' and 'erase' require --password and --confirm")
+			)
+			.arg(Arg::new("password")
+				.long("password")
+				.num_args(1)
+				.value_name("PASSWORD")
+				.help("password to set or to erase device with, up to 32 bytes")
+			)
+			.arg(Arg::new("master")
+				.long("master")
+				.action(ArgAction::SetTrue)
+				.help("use master password instead of the user one")
+			)
+			.arg(Arg::new("level")
+				.long("level")
+				.num_args(1)
+				.value_parser(["high", "maximum"])
+				.help("security level to set along with the user password (default: high);\nwith 'maximum', master password can only be used to erase the device, not to unlock it")
+			)
+			.arg(Arg::new("enhanced")
+				.long("enhanced")
+				.action(ArgAction::SetTrue)
+				.help("use enhanced security erase, which also overwrites reallocated sectors and such")
+			)
+			.arg(Arg::new("confirm")
+				.long("confirm")
+				.num_args(1)
+				.value_name("SERIAL")
+				.help("serial number of the device, to confirm that you really want to set the password or erase this particular device")
+			)
+	}
+
+	fn run(
+		&self,
+		_: &Option<&Path>,
+		dev: &Option<&DeviceArgument>,
+		args: &ArgMatches,
+	) {
+		let dev = dev.unwrap_or_else(|| {
+			// TODO show usage and whatnot
+			eprint!("<device> is required\n");
+			::std::process::exit(1);
+		});
+
+		let (dev, id): (&dyn Misc, _) = match dev {
+			#[cfg(not(target_os = "linux"))]
+			DeviceArgument::ATA(dev, id) => (dev, id),
+			DeviceArgument::SAT(dev, id) => (dev, id),
+			DeviceArgument::ATAPI(_, _) | DeviceArgument::SCSI(_) => {
+				eprint!("ATA Security is not available for ATAPI and SCSI devices\n");
+				::std::process::exit(1);
+			},
+		};
+
+		// unwrap: this argument is required
+		let action = args.get_one::<String>("action").unwrap().as_str();
+
+		if let Err(e) = run(dev, id, action, args) {
+			eprint!("Cannot {}: {}\n", action, e);
+			::std::process::exit(1);
+		}
+	}
+}
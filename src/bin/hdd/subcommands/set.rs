@@ -0,0 +1,136 @@
+use hdd::ata::misc::{self, Misc};
+
+use clap::{
+	Arg,
+	ArgMatches,
+	Command,
+};
+
+use crate::DeviceArgument;
+use super::Subcommand;
+
+use std::path::Path;
+
+fn arg_toggle(name: &'static str, help: &'static str) -> Arg {
+	Arg::new(name)
+		.long(name)
+		.num_args(1)
+		.value_parser(["on", "off"])
+		.help(help)
+}
+
+// accepts either 'off' or a level in decimal or 0x-prefixed hex form
+fn parse_level(s: &str, min: u8, max: u8) -> Result<Option<u8>, String> {
+	if s == "off" {
+		return Ok(None);
+	}
+
+	let level = if let Some(hex) = s.strip_prefix("0x") {
+		u8::from_str_radix(hex, 16)
+	} else {
+		s.parse::<u8>()
+	}.map_err(|e| format!("{}", e))?;
+
+	if level < min || level > max {
+		return Err(format!("level should be in range {}..{}, or 'off'", min, max));
+	}
+
+	Ok(Some(level))
+}
+
+pub struct Set {}
+impl Subcommand for Set {
+	fn subcommand(&self) -> Command {
+		Command::new("set")
+			.about("Changes device settings (ATA SET FEATURES)")
+			.arg(arg_toggle("write-cache", "enable or disable volatile write cache"))
+			.arg(arg_toggle("read-look-ahead", "enable or disable read look-ahead"))
+			.arg(arg_toggle("dipm", "enable or disable SATA Device-Initiated Interface Power Management"))
+			.arg(arg_toggle("devsleep", "enable or disable SATA Device Sleep"))
+			.arg(arg_toggle("sense-data", "enable or disable Sense Data Reporting, which makes errors of failed commands more descriptive"))
+			.arg(Arg::new("apm")
+				.long("apm")
+				.num_args(1)
+				.value_name("off|1..254")
+				.value_parser(|s: &str| parse_level(s, 0x01, 0xfe))
+				.help("set Advanced Power Management level (1: minimum power consumption with standby, 128: without standby, 254: maximum performance), or disable APM")
+			)
+			.arg(Arg::new("aam")
+				.long("aam")
+				.num_args(1)
+				.value_name("off|128..254")
+				.value_parser(|s: &str| parse_level(s, 0x80, 0xfe))
+				.help("set Automatic Acoustic Management level (128: quietest, 254: maximum performance), or disable AAM")
+			)
+	}
+
+	fn run(
+		&self,
+		_: &Option<&Path>,
+		dev: &Option<&DeviceArgument>,
+		args: &ArgMatches,
+	) {
+		let dev = dev.unwrap_or_else(|| {
+			// TODO show usage and whatnot
+			eprint!("<device> is required\n");
+			::std::process::exit(1);
+		});
+
+		let dev: &dyn Misc = match dev {
+			#[cfg(not(target_os = "linux"))]
+			DeviceArgument::ATA(dev, _) => dev,
+			DeviceArgument::SAT(dev, _) => dev,
+			DeviceArgument::ATAPI(dev, _) => dev,
+			DeviceArgument::SCSI(_) => {
+				eprint!("Changing settings of SCSI devices is not supported\n");
+				::std::process::exit(1);
+			},
+		};
+
+		let toggle = |name| args.get_one::<String>(name).map(|v| v == "on");
+
+		let mut changes: Vec<(&str, String, Result<(), misc::Error>)> = vec![];
+
+		if let Some(enable) = toggle("write-cache") {
+			changes.push(("Write cache", if enable { "enabled" } else { "disabled" }.to_string(), dev.set_write_cache(enable)));
+		}
+		if let Some(enable) = toggle("read-look-ahead") {
+			changes.push(("Read look-ahead", if enable { "enabled" } else { "disabled" }.to_string(), dev.set_read_look_ahead(enable)));
+		}
+		if let Some(enable) = toggle("dipm") {
+			changes.push(("DIPM", if enable { "enabled" } else { "disabled" }.to_string(), dev.set_dipm(enable)));
+		}
+		if let Some(enable) = toggle("devsleep") {
+			changes.push(("DevSleep", if enable { "enabled" } else { "disabled" }.to_string(), dev.set_devsleep(enable)));
+		}
+		if let Some(enable) = toggle("sense-data") {
+			changes.push(("Sense Data Reporting", if enable { "enabled" } else { "disabled" }.to_string(), dev.set_sense_data_reporting(enable)));
+		}
+		if let Some(&level) = args.get_one::<Option<u8>>("apm") {
+			changes.push(("APM", level.map(|l| format!("level {}", l)).unwrap_or("disabled".to_string()), dev.set_apm(level)));
+		}
+		if let Some(&level) = args.get_one::<Option<u8>>("aam") {
+			changes.push(("AAM", level.map(|l| format!("level {}", l)).unwrap_or("disabled".to_string()), dev.set_aam(level)));
+		}
+
+		if changes.is_empty() {
+			eprint!("Nothing to change\n");
+			::std::process::exit(1);
+		}
+
+		let mut failed = false;
+		for (what, state, result) in changes {
+			match result {
+				Ok(()) => print!("{}: {}\n", what, state),
+				Err(e) => {
+					eprint!("{}: cannot set to {}: {}\n", what, state, e);
+					failed = true;
+				},
+			}
+		}
+
+		if failed {
+			::std::process::exit(1);
+		}
+	}
+}
@@ -0,0 +1,24 @@
+/*!
+drivedb snapshot that is embedded into the crate at build time (see `embedded-drivedb` feature).
+*/
+
+use super::parser::Entry;
+
+include!(concat!(env!("OUT_DIR"), "/drivedb.rs"));
+
+/// `file` of the entries that come from the embedded snapshot
+pub const SOURCE: &str = "<built-in>";
+
+pub fn entries() -> Vec<Entry> {
+	ENTRIES.iter()
+		.map(|&(family, model, firmware, warning, presets, line)| Entry {
+			family: family.to_string(),
+			model: model.to_string(),
+			firmware: firmware.to_string(),
+			warning: warning.to_string(),
+			presets: presets.to_string(),
+			file: Some(SOURCE.to_string()),
+			line,
+		})
+		.collect()
+}
@@ -0,0 +1,126 @@
+/*!
+On-disk cache of parsed drivedb files.
+
+Cache files are named after the hash of the drivedb file contents (along with the version of this crate and of the cache format), so there's no need to track modification times or to invalidate anything: whenever drivedb file is updated, another cache file is used. Stale cache files are not removed though.
+
+Format is a simple little-endian binary one:
+
+- magic bytes `hdddb`, format version (u32), source hash (u64),
+- number of entries (u32),
+- entries: line (u32), followed by family, model, firmware, warning and presets, each a u32 length followed by the bytes of the string.
+*/
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+
+use super::parser::Entry;
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 5] = b"hdddb";
+
+/// Bump this whenever the format changes, or the same drivedb might get parsed differently (e.g. when preprocessor or parser learn new tricks).
+const VERSION: u32 = 1;
+
+// 64-bit FNV-1a: unlike `std::hash::DefaultHasher`, it is guaranteed to stay the same across Rust releases
+fn fnv1a(hash: u64, data: &[u8]) -> u64 {
+	data.iter().fold(hash, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Computes the key that cache file for drivedb file with contents `data` is looked up by.
+pub fn key(data: &[u8]) -> u64 {
+	let hash = 0xcbf2_9ce4_8422_2325;
+	let hash = fnv1a(hash, &VERSION.to_le_bytes());
+	let hash = fnv1a(hash, env!("CARGO_PKG_VERSION").as_bytes());
+	fnv1a(hash, data)
+}
+
+fn path(dir: &Path, key: u64) -> PathBuf {
+	dir.join(format!("drivedb-{:016x}.cache", key))
+}
+
+fn read_string<R: Read>(r: &mut R) -> io::Result<String> {
+	let len = r.read_u32::<LittleEndian>()? as u64;
+	// not allocating `len` bytes upfront, in case the file is damaged and `len` is huge
+	let mut buf = vec![];
+	r.take(len).read_to_end(&mut buf)?;
+	if buf.len() as u64 != len {
+		return Err(io::ErrorKind::UnexpectedEof.into());
+	}
+	String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+	w.write_u32::<LittleEndian>(s.len() as u32)?;
+	w.write_all(s.as_bytes())
+}
+
+/// Reads entries from the cache in `dir`; returns `None` if there's no usable cache file for the `key`.
+pub fn read(dir: &Path, key: u64) -> Option<Vec<Entry>> {
+	let read = || -> io::Result<Option<Vec<Entry>>> {
+		let mut r = BufReader::new(File::open(path(dir, key))?);
+
+		let mut magic = [0; 5];
+		r.read_exact(&mut magic)?;
+		if &magic != MAGIC || r.read_u32::<LittleEndian>()? != VERSION || r.read_u64::<LittleEndian>()? != key {
+			return Ok(None);
+		}
+
+		let count = r.read_u32::<LittleEndian>()?;
+		let mut entries = Vec::with_capacity(count as usize);
+		for _ in 0 .. count {
+			let line = r.read_u32::<LittleEndian>()? as usize;
+			entries.push(Entry {
+				family: read_string(&mut r)?,
+				model: read_string(&mut r)?,
+				firmware: read_string(&mut r)?,
+				warning: read_string(&mut r)?,
+				presets: read_string(&mut r)?,
+				file: None,
+				line,
+			});
+		}
+		Ok(Some(entries))
+	};
+
+	match read() {
+		Ok(entries) => entries,
+		Err(e) => {
+			// missing file is the most common reason, and is not worth reporting
+			if e.kind() != io::ErrorKind::NotFound {
+				warn!("cannot read drivedb cache {}: {}", path(dir, key).display(), e);
+			}
+			None
+		},
+	}
+}
+
+/// Writes `entries` into the cache in `dir`, creating the directory if necessary.
+pub fn write(dir: &Path, key: u64, entries: &[Entry]) -> io::Result<()> {
+	fs::create_dir_all(dir)?;
+
+	// write the whole thing under a temporary name first, so that concurrent readers never see incomplete file
+	let target = path(dir, key);
+	let tmp = target.with_extension(format!("tmp.{}", ::std::process::id()));
+
+	let write = || -> io::Result<()> {
+		let mut w = BufWriter::new(File::create(&tmp)?);
+		w.write_all(MAGIC)?;
+		w.write_u32::<LittleEndian>(VERSION)?;
+		w.write_u64::<LittleEndian>(key)?;
+		w.write_u32::<LittleEndian>(entries.len() as u32)?;
+		for e in entries {
+			w.write_u32::<LittleEndian>(e.line as u32)?;
+			for s in [&e.family, &e.model, &e.firmware, &e.warning, &e.presets] {
+				write_string(&mut w, s)?;
+			}
+		}
+		w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+		fs::rename(&tmp, &target)
+	};
+
+	write().inspect_err(|_| {
+		let _ = fs::remove_file(&tmp);
+	})
+}
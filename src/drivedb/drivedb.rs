@@ -1,75 +1,296 @@
-use super::{presets, Attribute};
+use super::{ere, presets, Attribute, FirmwareBug, LogParameter};
+use super::vendor_attribute::Type;
 use super::parser::Entry;
 use regex;
-use regex::bytes::{RegexSet, RegexSetBuilder};
-use std::collections::HashSet;
+use regex::bytes::{Regex, RegexBuilder};
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
 
 use crate::ata::data::id;
+use crate::scsi::data::inquiry;
 
 /**
 Drive database that hosts its entries and allows to search for relevant data.
 
+Database is immutable and reference-counted, so clones are cheap and share everything, including compiled regexes; it can also be shared between threads as is. To replace the database on the fly, see [`SharedDriveDB`](struct.SharedDriveDB.html).
+
+Besides the usual entries for ATA drives, database can also hold entries for SCSI and NVMe drives. These are written just like any other drivedb entry, but with their model patterns prefixed by `SCSI:` or `NVMe:` (much like smartmontools' `USB:` entries):
+
+- model pattern of a SCSI entry is matched against INQUIRY vendor and product identification, joined with a single space (e.g. `"SCSI:SEAGATE ST4000NM0023"`), and firmware pattern is matched against product revision level,
+- model and firmware patterns of an NVMe entry are matched against model number and firmware revision from Identify Controller data structure.
+
+Presets of such entries only make sense for `-l` options that describe vendor-specific log parameters (see [`LogParameter`](struct.LogParameter.html)), and these entries are not merged with the default one. smartctl does not understand any of this, so it's better to keep these entries in a separate file that only this crate reads.
+
 USB entries are currently not supported.
 */
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DriveDB {
-	entries: Vec<Entry>,
+	inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+	ata: Table,
+	scsi: Table,
+	nvme: Table,
 
 	// pre-found default entry: most likely it will be used right away, so it's not that harmful,
 	// and it's better to have one if it's going to be requested multiple times
 	default: Option<Entry>,
 
-	// precompiled RegexSets are often faster than simple regexes lazily compiled one by one on demand until the first match
-	// (even if RegexSet compilation time is taken into account!),
-	// and are a must if multiple lookups are about to be performed
-	model_regexes: RegexSet,
-	firmware_regexes: RegexSet,
+	invalid: Vec<InvalidEntry>,
+}
+
+// entries of one kind (ATA, SCSI or NVMe), in lookup order
+#[derive(Debug)]
+struct Table {
+	entries: Vec<Entry>,
+
+	// patterns of `entries`, in the same order
+	//
+	// compiling regexes of all the entries takes much longer than a single lookup (and short-lived processes only need one),
+	// so regexes are only compiled on demand, and only for the entries that pass literal prefix check;
+	// compiled regexes are kept for subsequent lookups
+	patterns: Vec<Patterns>,
+}
+
+/// drivedb entry that was left out of the database because one of its patterns cannot be used
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct InvalidEntry {
+	/// file the entry was loaded from, if known
+	pub file: Option<String>,
+	/// line of the file where the entry starts
+	pub line: usize,
+	pub family: String,
+	/// offending pattern, as it appears in drivedb
+	pub pattern: String,
+	pub reason: String,
+}
+
+impl InvalidEntry {
+	fn new(entry: &Entry, pattern: &str, reason: String) -> Self {
+		InvalidEntry {
+			file: entry.file.clone(),
+			line: entry.line,
+			family: entry.family.clone(),
+			pattern: pattern.to_string(),
+			reason,
+		}
+	}
+
+	fn warn(&self) {
+		warn!("ignoring drivedb entry {:?} ({}:{}): cannot use pattern {:?}: {}",
+			self.family, self.file.as_deref().unwrap_or("?"), self.line, self.pattern, self.reason);
+	}
+}
+
+// translated model and firmware patterns of an entry
+#[derive(Debug)]
+struct Patterns {
+	model: String,
+	firmware: String,
+	// every model that matches starts with this
+	prefix: String,
+	// `None` if regex crate rejects any of the patterns
+	compiled: OnceLock<Option<(Regex, Regex)>>,
+}
+
+impl Patterns {
+	fn compiled(&self, entry: &Entry) -> Option<&(Regex, Regex)> {
+		self.compiled.get_or_init(|| match compile(entry, self) {
+			Ok(regexes) => Some(regexes),
+			Err(e) => {
+				e.warn();
+				None
+			},
+		}).as_ref()
+	}
+
+	fn is_match(&self, entry: &Entry, model: &str, firmware: &str) -> bool {
+		model.starts_with(&self.prefix) && match self.compiled(entry) {
+			Some((model_re, firmware_re)) => model_re.is_match(model.as_bytes()) && firmware_re.is_match(firmware.as_bytes()),
+			None => false,
+		}
+	}
+}
+
+fn translate(entry: &Entry) -> Result<Patterns, InvalidEntry> {
+	let model = ere::anchored(&entry.model)
+		.map_err(|reason| InvalidEntry::new(entry, &entry.model, reason))?;
+	// empty firmware pattern matches any firmware
+	let firmware = if entry.firmware.is_empty() {
+		"".to_string()
+	} else {
+		ere::anchored(&entry.firmware)
+			.map_err(|reason| InvalidEntry::new(entry, &entry.firmware, reason))?
+	};
+	Ok(Patterns {
+		model,
+		firmware,
+		prefix: ere::literal_prefix(&entry.model),
+		compiled: OnceLock::new(),
+	})
+}
+
+// compiles translated patterns of an entry, in case regex crate accepts them
+fn compile(entry: &Entry, patterns: &Patterns) -> Result<(Regex, Regex), InvalidEntry> {
+	// model and firmware are expected to be ascii strings, no need to try matching unicode characters
+	// hence `unicode(false)` and use of `regex::bytes::*` instead of `regex::*`
+	let compile = |original: &str, translated: &str| RegexBuilder::new(translated).unicode(false).build()
+		.map_err(|e| InvalidEntry::new(entry, original, e.to_string()));
+	Ok((
+		compile(&entry.model, &patterns.model)?,
+		compile(&entry.firmware, &patterns.firmware)?,
+	))
+}
+
+impl Table {
+	fn new(entries: Vec<Entry>, invalid: &mut Vec<InvalidEntry>) -> Self {
+		// drivedb patterns are POSIX extended regexes; ones that cannot be translated only take their own entries down, not the whole database
+		let (entries, patterns) = entries.into_iter()
+			.filter_map(|e| match translate(&e) {
+				Ok(patterns) => Some((e, patterns)),
+				Err(err) => { invalid.push(err); None },
+			})
+			.unzip();
+
+		Table { entries, patterns }
+	}
+
+	fn find(&self, model: &str, firmware: &str) -> Option<(usize, &Entry)> {
+		// find the first match (if any), which is consistent with smartmontools
+		self.entries.iter().zip(self.patterns.iter())
+			.position(|(entry, patterns)| patterns.is_match(entry, model, firmware))
+			.map(|index| (index, &self.entries[index]))
+	}
+
+	fn compile_all(&self) -> impl Iterator<Item = InvalidEntry> + '_ {
+		self.entries.iter().zip(self.patterns.iter())
+			.filter(|(entry, patterns)| patterns.compiled(entry).is_none())
+			// compiling once again is the easiest way to get the error back, and it only happens for broken entries
+			.filter_map(|(entry, patterns)| compile(entry, patterns).err())
+	}
+
+	fn shadowed(&self) -> Vec<Issue> {
+		let mut issues = vec![];
+		for (i, entry) in self.entries.iter().enumerate() {
+			let shadowed_by = self.entries[.. i].iter().zip(self.patterns.iter())
+				.position(|(earlier, patterns)| match patterns.compiled(earlier) {
+					Some((model_re, firmware_re)) =>
+						covers(&earlier.model, model_re, &entry.model)
+						&& covers(&earlier.firmware, firmware_re, &entry.firmware),
+					None => false,
+				});
+			if let Some(j) = shadowed_by {
+				issues.push(Issue::Shadowed {
+					entry: EntryMatch::new(Some(i), entry, vec![]),
+					by: EntryMatch::new(Some(j), &self.entries[j], vec![]),
+				});
+			}
+		}
+		issues
+	}
 }
 
 impl DriveDB {
-	pub(crate) fn new(entries: Vec<Entry>) -> Result<Self, regex::Error> {
-		let entries = entries.into_iter()
-			// USB ID entries are parsed differently; also, we don't support USB devices yet
-			.filter(|e| ! e.model.starts_with("USB:"));
-
-		// filter out all entries marked as default: they're of no use fo self.find()
-		// (yes, there might be multiple default entries from e.g. additional drivedb files)
-		let (default, entries): (Vec<_>, Vec<_>) = entries.partition(|e| e.family == "DEFAULT");
-
-		// pick the first default entry, if any, or set to None
-		let default = default.into_iter().next();
-
-		// model and firmware are expected to be ascii strings, no need to try matching unicode characters
-		// hence `unicode(false)` and use of `regex::bytes::*` instead of `regex::*`
-		let model_regexes = RegexSetBuilder::new(entries.iter()
-			.map(|e| format!("^{}$", e.model))
-		).unicode(false).build()?;
-		let firmware_regexes = RegexSetBuilder::new(entries.iter()
-			.map(|e|
-				if e.firmware.is_empty() {
-					"".to_string()
-				} else {
-					format!("^{}$", e.firmware)
+	pub(crate) fn new(entries: Vec<Entry>) -> Self {
+		let mut ata = vec![];
+		let mut scsi = vec![];
+		let mut nvme = vec![];
+		let mut default = None;
+
+		for mut e in entries {
+			if e.model.starts_with("USB:") {
+				// USB ID entries are parsed differently; also, we don't support USB devices yet
+				continue;
+			} else if e.family == "DEFAULT" {
+				// default entries are of no use for lookups; pick the first one, if any
+				// (yes, there might be multiple default entries from e.g. additional drivedb files)
+				if default.is_none() {
+					default = Some(e);
 				}
-			)
-		).unicode(false).build()?;
+			} else if let Some(model) = e.model.strip_prefix("SCSI:") {
+				e.model = model.trim_start().to_string();
+				scsi.push(e);
+			} else if let Some(model) = e.model.strip_prefix("NVMe:") {
+				e.model = model.trim_start().to_string();
+				nvme.push(e);
+			} else {
+				ata.push(e);
+			}
+		}
 
-		Ok(DriveDB {
-			entries,
+		let mut invalid = vec![];
+		let ata = Table::new(ata, &mut invalid);
+		let scsi = Table::new(scsi, &mut invalid);
+		let nvme = Table::new(nvme, &mut invalid);
+
+		for e in invalid.iter() {
+			e.warn();
+		}
+
+		DriveDB { inner: Arc::new(Inner {
+			ata,
+			scsi,
+			nvme,
 			default,
-			model_regexes,
-			firmware_regexes,
-		})
+			invalid,
+		})}
+	}
+
+	fn tables(&self) -> [&Table; 3] {
+		[&self.inner.ata, &self.inner.scsi, &self.inner.nvme]
+	}
+
+	/**
+	Returns entries that were left out of the database because their model or firmware patterns cannot be used.
+
+	Patterns are only compiled on demand, so this only lists the ones that cannot be translated from POSIX syntax; see [`compile_all()`](#method.compile_all) for the rest.
+	*/
+	pub fn invalid_entries(&self) -> &[InvalidEntry] {
+		&self.inner.invalid
 	}
 
-	fn find(&self, model: &str, firmware: &str) -> Option<&Entry> {
-		let models: HashSet<_> = self.model_regexes.matches(model.as_bytes()).iter().collect();
-		let firmwares: HashSet<_> = self.firmware_regexes.matches(firmware.as_bytes()).iter().collect();
+	/**
+	Compiles patterns of every entry right away, returning entries with patterns that regex crate rejects (these entries never match anything).
 
-		// find the first match (if any)
-		models.intersection(&firmwares)
-			.min()
-			.map(|&index| &self.entries[index])
+	Use this to validate the database, or to avoid compilation delays during lookups in long-running processes.
+	*/
+	pub fn compile_all(&self) -> Vec<InvalidEntry> {
+		self.tables().into_iter()
+			.flat_map(|table| table.compile_all())
+			.collect()
+	}
+
+	/**
+	Checks the database for entries that are broken or useless:
+
+	- entries with patterns that cannot be translated or compiled (see [`invalid_entries()`](#method.invalid_entries) and [`compile_all()`](#method.compile_all)),
+	- entries (including the default one) with preset options that cannot be parsed,
+	- entries that can never match because some earlier entry matches everything they do. Telling whether one regex is a superset of another one is a bit too much for this crate though, so this only considers entries with literal patterns, or patterns identical to the ones of earlier entries.
+	*/
+	pub fn lint(&self) -> Vec<Issue> {
+		let mut issues: Vec<_> = self.inner.invalid.iter().cloned()
+			.chain(self.compile_all())
+			.map(Issue::InvalidPattern)
+			.collect();
+
+		let entries = self.tables().into_iter()
+			.flat_map(|table| table.entries.iter().enumerate().map(|(i, e)| (Some(i), e)));
+		for (index, entry) in self.inner.default.iter().map(|e| (None, e)).chain(entries) {
+			let invalid = presets::parse(&entry.presets).invalid;
+			if !invalid.is_empty() {
+				issues.push(Issue::InvalidPresets(EntryMatch::new(index, entry, invalid)));
+			}
+		}
+
+		// entries of different kinds never compete with each other
+		for table in self.tables() {
+			issues.extend(table.shadowed());
+		}
+
+		issues
 	}
 
 	/**
@@ -78,48 +299,147 @@ impl DriveDB {
 	Return value is a merge between the default entry and the first match; if multiple entries match the `id`, the first one is used (this is consistent with smartmontools' `lookup_drive` function).
 	`extra_attributes` are also appended to the list of presets afterwards.
 	*/
-	pub fn render_meta(&self, id: &id::Id, extra_attributes: &Vec<Attribute>) -> DriveMeta<'_> {
-		let mut m = DriveMeta {
-			family: None,
-			warning: None,
-			presets: Vec::<Attribute>::new(),
+	pub fn render_meta(&self, id: &id::Id, extra_attributes: &Vec<Attribute>) -> DriveMeta {
+		let drivetype = {
+			use crate::ata::data::id::RPM::*;
+			use super::vendor_attribute::Type::*;
+			match id.rpm {
+				RPM(_) => Some(HDD),
+				NonRotating => Some(SSD),
+				Unknown => None,
+			}
 		};
+		self.lookup(&id.model, &id.firmware, drivetype, extra_attributes)
+	}
 
-		// TODO show somehow whether default entry was found or not, or ask caller for the default entry
-		if let Some(default) = &self.default {
-			// TODO show somehow whether preset is valid or not
-			if let Some(presets) = presets::parse(&default.presets) {
-				m.presets.extend(presets);
-			}
-		}
+	/**
+	Same as [`render_meta()`](#method.render_meta), but for arbitrary model and firmware strings, which is handy when there's no actual device around.
 
-		if let Some(entry) = self.find(&id.model, &id.firmware) {
-			// TODO show somehow whether preset is valid or not
-			if let Some(presets) = presets::parse(&entry.presets) {
-				m.presets.extend(presets);
-			}
+	`drivetype` is used to filter out attribute descriptions that are specific to other type of devices; if it is `None`, all the type-specific descriptions are filtered out.
+	*/
+	pub fn lookup(&self, model: &str, firmware: &str, drivetype: Option<Type>, extra_attributes: &[Attribute]) -> DriveMeta {
+		let mut m = DriveMeta::default();
 
-			m.family = Some(&entry.family);
-			m.warning = if ! entry.warning.is_empty() { Some(&entry.warning) } else { None };
+		if let Some(default) = &self.inner.default {
+			let presets = presets::parse(&default.presets);
+			m.presets.extend(presets.attributes);
+			m.firmware_bugs.extend(presets.firmware_bugs);
+			m.log_parameters.extend(presets.log_parameters);
+
+			m.default = Some(EntryMatch::new(None, default, presets.invalid));
+		}
+
+		if let Some((index, entry)) = self.inner.ata.find(model, firmware) {
+			m.apply(model, index, entry);
 		}
 
 		m.presets.extend(extra_attributes.iter().map(|a| a.clone()));
-		m.presets = filter_presets(id, m.presets);
+		m.presets = filter_presets(drivetype, m.presets);
 		return m;
 	}
+
+	/**
+	Matches given SCSI INQUIRY data against SCSI entries of the database (see [`DriveDB`](struct.DriveDB.html) on these). Unlike ATA lookups, the default entry is not used here.
+	*/
+	pub fn render_scsi_meta(&self, inquiry: &inquiry::Inquiry) -> DriveMeta {
+		self.lookup_scsi(&inquiry.vendor_id, &inquiry.product_id, &inquiry.product_rev)
+	}
+
+	/// Same as [`render_scsi_meta()`](#method.render_scsi_meta), but for arbitrary vendor, product and revision strings.
+	pub fn lookup_scsi(&self, vendor: &str, product: &str, revision: &str) -> DriveMeta {
+		let mut m = DriveMeta::default();
+		let model = format!("{} {}", vendor.trim(), product.trim());
+		let model = model.trim();
+		if let Some((index, entry)) = self.inner.scsi.find(model, revision.trim()) {
+			m.apply(model, index, entry);
+		}
+		m
+	}
+
+	/// Matches NVMe model number and firmware revision against NVMe entries of the database (see [`DriveDB`](struct.DriveDB.html) on these). Unlike ATA lookups, the default entry is not used here.
+	pub fn lookup_nvme(&self, model: &str, firmware: &str) -> DriveMeta {
+		let mut m = DriveMeta::default();
+		if let Some((index, entry)) = self.inner.nvme.find(model.trim(), firmware.trim()) {
+			m.apply(model, index, entry);
+		}
+		m
+	}
+}
+
+/**
+Drive database that can be replaced while in use, e.g. when long-running process notices that drivedb files were updated.
+
+Lookups are done on snapshots returned by [`get()`](#method.get): once the database is replaced, lookups that are already in progress carry on with the old one, which is dropped as soon as the last of these snapshots is gone.
+
+## Example
+
+```
+use hdd::drivedb::{Loader, SharedDriveDB};
+
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let db = SharedDriveDB::new(Loader::new().db());
+
+// each thread gets its own handle
+let handle = db.clone();
+let lookup = std::thread::spawn(move || {
+	let meta = handle.get().lookup("WDC WD5000AAKS-00A7B2", "01.03B01", None, &[]);
+	meta.family
+});
+
+// e.g. upon SIGHUP
+let old = db.replace(Loader::new().db());
+
+lookup.join().unwrap();
+# drop(old);
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Clone)]
+pub struct SharedDriveDB {
+	current: Arc<RwLock<DriveDB>>,
 }
 
-fn filter_presets(id: &id::Id, preset: Vec<Attribute>) -> Vec<Attribute> {
-	let drivetype = {
-		use crate::ata::data::id::RPM::*;
-		use super::vendor_attribute::Type::*;
-		match id.rpm {
-			RPM(_) => Some(HDD),
-			NonRotating => Some(SSD),
-			Unknown => None,
+impl SharedDriveDB {
+	pub fn new(db: DriveDB) -> Self {
+		SharedDriveDB {
+			current: Arc::new(RwLock::new(db)),
 		}
-	};
+	}
+
+	/// Returns the current database. This is cheap, and the lock is only held for as long as it takes to clone the `Arc` inside the `DriveDB`.
+	pub fn get(&self) -> DriveDB {
+		// lock cannot actually be poisoned: nothing that is done while holding it panics
+		self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+	}
+
+	/// Replaces the database with the new one, returning the old one.
+	pub fn replace(&self, db: DriveDB) -> DriveDB {
+		let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+		::std::mem::replace(&mut *current, db)
+	}
+}
+
+// the whole point of all the above is to share these between threads; make sure it stays possible
+const _: fn() = || {
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<DriveDB>();
+	assert_send_sync::<DriveMeta>();
+};
+
+// whether pattern `a` (and its compiled counterpart) matches everything that pattern `b` does; see `DriveDB::lint()` for the limitations
+fn covers(a: &str, a_regex: &Regex, b: &str) -> bool {
+	// empty firmware pattern matches anything
+	if a.is_empty() || a == b {
+		return true;
+	}
+	match ere::literal(b) {
+		Some(literal) if !b.is_empty() => a_regex.is_match(literal.as_bytes()),
+		_ => false,
+	}
+}
 
+fn filter_presets(drivetype: Option<Type>, preset: Vec<Attribute>) -> Vec<Attribute> {
 	#[cfg_attr(feature = "cargo-clippy", allow(match_same_arms))]
 	preset.into_iter().filter(|attr| match (attr.drivetype, drivetype) {
 		// this attribute is not type-specific
@@ -133,28 +453,141 @@ fn filter_presets(id: &id::Id, preset: Vec<Attribute>) -> Vec<Attribute> {
 	}).collect()
 }
 
-/// Drive-related data that cannot be queried from the drive itself (model family, attribute presets etc.)
-#[derive(Debug)]
-pub struct DriveMeta<'a> {
+/// Problem with drivedb entry, see [`DriveDB::lint()`](struct.DriveDB.html#method.lint).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Issue {
+	/// entry pattern cannot be used, thus the entry is ignored
+	InvalidPattern(InvalidEntry),
+	/// entry has preset options that cannot be parsed, see `invalid_presets`
+	InvalidPresets(EntryMatch),
+	/// entry never matches anything, because earlier entry `by` matches everything it does
+	Shadowed {
+		entry: EntryMatch,
+		by: EntryMatch,
+	},
+}
+
+impl fmt::Display for Issue {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Issue::InvalidPattern(e) => write!(f, "{}:{}: {:?}: invalid pattern {:?}: {}",
+				e.file.as_deref().unwrap_or("?"), e.line, e.family, e.pattern, e.reason),
+			Issue::InvalidPresets(e) => write!(f, "{}: {:?}: invalid presets: {}",
+				e, e.family, e.invalid_presets.join(", ")),
+			Issue::Shadowed { entry, by } => write!(f, "{}: {:?}: never matches, shadowed by {:?} at {}",
+				entry, entry.family, by.family, by),
+		}
+	}
+}
+
+/// Tells which drivedb entry contributed to [`DriveMeta`](struct.DriveMeta.html), and where it came from.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct EntryMatch {
+	/// position of the entry among the database entries of the same kind (ATA, SCSI or NVMe), in lookup order (entries from additional files go first); `None` for the default entry, which does not take part in the lookup
+	pub index: Option<usize>,
+	/// file the entry was loaded from, if known
+	pub file: Option<String>,
+	/// line of the file where the entry starts
+	pub line: usize,
+	pub family: String,
+	/// options of the entry's preset string that cannot be parsed or are not supported, and were thus skipped
+	pub invalid_presets: Vec<String>,
+}
+
+impl EntryMatch {
+	fn new(index: Option<usize>, entry: &Entry, invalid_presets: Vec<String>) -> Self {
+		EntryMatch {
+			index,
+			file: entry.file.clone(),
+			line: entry.line,
+			family: entry.family.clone(),
+			invalid_presets,
+		}
+	}
+}
+
+impl fmt::Display for EntryMatch {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match &self.file {
+			Some(file) => write!(f, "{}:{}", file, self.line),
+			None => write!(f, "line {}", self.line),
+		}
+	}
+}
+
+/**
+Drive-related data that cannot be queried from the drive itself (model family, attribute presets etc.)
+
+Unlike the database itself, it is tiny, and does not borrow anything from the database, so it can be kept around (e.g. cached for each device) for as long as needed.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct DriveMeta {
 	/// > Informal string about the model family/series of a device.
-	pub family: Option<&'a String>,
+	pub family: Option<String>,
 
 	/// > A message that may be displayed for matching drives.
 	/// > For example, to inform the user that they may need to apply a firmware patch.
-	pub warning: Option<&'a String>,
+	pub warning: Option<String>,
+
+	/// entry that matched the drive, if any
+	pub entry: Option<EntryMatch>,
+	/// default entry, if the database has one; its presets are applied before the ones of the matched entry
+	pub default: Option<EntryMatch>,
 
 	/// SMART attribute descriptions
 	presets: Vec<Attribute>,
+
+	firmware_bugs: Vec<FirmwareBug>,
+
+	log_parameters: Vec<LogParameter>,
 }
 
-impl<'a> DriveMeta<'a> {
+impl DriveMeta {
+	// merges presets and the rest of the data from the `entry` that matched the `model`
+	fn apply(&mut self, model: &str, index: usize, entry: &Entry) {
+		let presets = presets::parse(&entry.presets);
+		self.presets.extend(presets.attributes);
+		self.firmware_bugs.extend(presets.firmware_bugs);
+		self.log_parameters.extend(presets.log_parameters);
+
+		self.family = Some(entry.family.clone());
+		self.warning = if ! entry.warning.is_empty() { Some(entry.warning.clone()) } else { None };
+
+		let found = EntryMatch::new(Some(index), entry, presets.invalid);
+		debug!("drivedb: {:?} matches entry at {}", model, found);
+		self.entry = Some(found);
+	}
+
+	/// Returns whether drivedb entry says that the drive suffers from a particular firmware `bug`.
+	pub fn has_firmware_bug(&self, bug: FirmwareBug) -> bool {
+		self.firmware_bugs.contains(&bug)
+	}
+
+	/// Returns all the firmware bugs that drivedb entries tell about.
+	pub fn firmware_bugs(&self) -> &[FirmwareBug] {
+		&self.firmware_bugs
+	}
+
+	/// Returns descriptions of SCSI log parameters, in the order they appear in drivedb entries. Later descriptions override earlier ones for the same parameter, see [`render_log_parameter()`](#method.render_log_parameter).
+	pub fn log_parameters(&self) -> &[LogParameter] {
+		&self.log_parameters
+	}
+
+	/// Returns description of parameter `code` of SCSI log page `page`, if there's one.
+	pub fn render_log_parameter(&self, page: u8, code: u16) -> Option<&LogParameter> {
+		self.log_parameters.iter().rev()
+			.find(|param| param.page == page && param.code == code)
+	}
+
 	/*
 	Attributes are never looked up; they must be rendered for a number of reasons:
 	- description might match all attributes at once (`-v N,…`, represented with `attr.id` of `None`),
 	- description might only update data format, leaving previously defined name and drive type intact.
 	*/
 	/// Renders attribute description for a particular attribute `id`.
-	pub fn render_attribute(&'a self, id: u8) -> Option<Attribute> {
+	pub fn render_attribute(&self, id: u8) -> Option<Attribute> {
 		let mut out = None;
 
 		for new in self.presets.iter() {
@@ -168,6 +601,7 @@ impl<'a> DriveMeta<'a> {
 				Some(ref mut old) => {
 					old.format = new.format.clone();
 					old.byte_order = new.byte_order.clone();
+					old.increasing = new.increasing;
 					if new.name.is_some() {
 						old.name = new.name.clone();
 					}
@@ -0,0 +1,352 @@
+/*!
+Translation of POSIX extended regular expressions into the syntax of [regex](https://docs.rs/regex) crate.
+
+drivedb patterns are meant for `regcomp(3)` with `REG_EXTENDED`, and while most of them look just the same in both syntaxes, some things do not:
+
+* backslash is a literal character inside bracket expressions, and so are `[`, `&&`, `~~` and `--`, which mean nested classes and set operations for the regex crate;
+* `]` is a literal if it goes first in a bracket expression;
+* equivalence classes (`[=a=]`) and collating symbols (`[.a.]`) are not supported by the regex crate at all;
+* `{` that does not start a valid interval is a literal, as are repetition operators with nothing to repeat (GNU libc behaviour);
+* repeated repetition operators (`a**`, `a+?`) are either syntax errors or mean lazy repetition for the regex crate;
+* `\<` and `\>` (GNU extensions) are word boundaries.
+
+As for leftmost-longest semantics of POSIX regexes: drivedb patterns are always matched against the whole string (see [`anchored()`](fn.anchored.html)), and in that case it makes no difference which of the alternatives is preferred, as long as there's a match at all.
+
+## Example
+
+```
+use hdd::drivedb::ere::translate;
+
+assert_eq!(translate("ST3[0-9]+AS").unwrap(), "ST3[0-9]+AS");
+assert_eq!(translate(r"[[:alnum:]\-]+").unwrap(), r"[[:alnum:]\\\-]+");
+assert_eq!(translate("[]a]").unwrap(), r"[\]a]");
+assert_eq!(translate("a{").unwrap(), r"a\{");
+assert_eq!(translate("*a**").unwrap(), r"\*(?:a*)*");
+assert_eq!(translate("(WDC|HGST) .*").unwrap(), "(?:WDC|HGST) .*");
+// only characters that are special for the regex crate are escaped in bracket expressions, it rejects the rest
+assert_eq!(translate("[<>.]").unwrap(), "[<>.]");
+assert!(regex::Regex::new(&hdd::drivedb::ere::anchored("[<>]").unwrap()).is_ok());
+
+// back-references are not a part of POSIX ERE, and regex crate does not support them anyway
+assert!(translate(r"(a)\1").is_err());
+```
+*/
+
+/// Names of character classes that can be used in bracket expressions (`[[:alpha:]]`); the regex crate supports the very same set.
+const CLASSES: [&str; 12] = ["alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space", "upper", "xdigit"];
+
+// escapes character for use outside of bracket expressions
+fn push_literal(out: &mut String, c: char) {
+	out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+}
+
+// escapes character for use inside of bracket expressions; the regex crate rejects escapes of anything but its special characters there (e.g. `\<`)
+fn push_class_literal(out: &mut String, c: char) {
+	if matches!(c, '\\' | '[' | ']' | '^' | '-' | '&' | '~') {
+		out.push('\\');
+	}
+	out.push(c);
+}
+
+// parses `[:name:]`, `[=c=]` or `[.c.]` at the start of `chars`, returning what is inside of it; `chars` should point to the opening `[`
+fn bracket_term(chars: &[char], delim: char) -> Option<(String, usize)> {
+	if chars.len() < 2 || chars[0] != '[' || chars[1] != delim {
+		return None;
+	}
+	(2 .. chars.len().saturating_sub(1))
+		.find(|&i| chars[i] == delim && chars[i + 1] == ']')
+		.map(|end| (chars[2 .. end].iter().collect(), end + 2))
+}
+
+// single-character term of a bracket expression that might be a range endpoint, along with the number of chars consumed
+fn bracket_char(chars: &[char]) -> Result<Option<(char, usize)>, String> {
+	for delim in ['=', '.'] {
+		if let Some((name, len)) = bracket_term(chars, delim) {
+			let mut name_chars = name.chars();
+			return match (name_chars.next(), name_chars.next()) {
+				(Some(c), None) => Ok(Some((c, len))),
+				_ => Err(format!("multi-character collating element [{}{}{}] is not supported", delim, name, delim)),
+			};
+		}
+	}
+	match chars.first() {
+		// that's a character class or the end of the expression, and it cannot be used as a range endpoint
+		Some('[') if chars.get(1) == Some(&':') => Ok(None),
+		Some(']') | None => Ok(None),
+		Some(&c) => Ok(Some((c, 1))),
+	}
+}
+
+// translates bracket expression; `chars` point right after the opening `[`, and the number of chars consumed (including the closing `]`) is returned
+fn bracket(chars: &[char], out: &mut String) -> Result<usize, String> {
+	let mut i = 0;
+	out.push('[');
+
+	if chars.get(i) == Some(&'^') {
+		out.push('^');
+		i += 1;
+	}
+
+	// `]` right after `[` or `[^` is a literal
+	let mut first = true;
+	loop {
+		match chars.get(i) {
+			None => return Err("unterminated bracket expression".to_string()),
+			Some(']') if !first => break,
+			_ => (),
+		}
+		first = false;
+
+		if let Some((name, len)) = bracket_term(&chars[i ..], ':') {
+			if !CLASSES.contains(&name.as_str()) {
+				return Err(format!("unknown character class [:{}:]", name));
+			}
+			out.push_str(&format!("[:{}:]", name));
+			i += len;
+			continue;
+		}
+
+		let (start, len) = match bracket_char(&chars[i ..])? {
+			Some(x) => x,
+			// can only be `]` at the very beginning, everything else was handled above
+			None => (chars[i], 1),
+		};
+		i += len;
+
+		// `-` is a literal if it goes last, otherwise that's a range
+		if chars.get(i) == Some(&'-') && chars.get(i + 1).is_some_and(|&c| c != ']') {
+			let (end, len) = bracket_char(&chars[i + 1 ..])?
+				.ok_or_else(|| "invalid range end in bracket expression".to_string())?;
+			if end < start {
+				return Err(format!("invalid range {}-{} in bracket expression", start, end));
+			}
+			push_class_literal(out, start);
+			out.push('-');
+			push_class_literal(out, end);
+			i += 1 + len;
+		} else {
+			push_class_literal(out, start);
+		}
+	}
+
+	out.push(']');
+	Ok(i + 1)
+}
+
+// parses interval (`{m}`, `{m,}`, `{m,n}` or GNU `{,n}`) at the start of `chars`, which point right after the opening `{`
+fn interval(chars: &[char]) -> Option<(String, usize)> {
+	let end = chars.iter().position(|&c| c == '}')?;
+	let body: String = chars[.. end].iter().collect();
+
+	let valid = |s: &str| !s.is_empty() && s.len() <= 3 && s.chars().all(|c| c.is_ascii_digit());
+	let body = match body.split_once(',') {
+		None if valid(&body) => body,
+		Some((min, max)) if (valid(min) || min.is_empty()) && (valid(max) || max.is_empty()) && !(min.is_empty() && max.is_empty()) => {
+			if !max.is_empty() && min.parse::<u16>().unwrap_or(0) > max.parse::<u16>().unwrap() {
+				return None;
+			}
+			format!("{},{}", if min.is_empty() { "0" } else { min }, max)
+		},
+		_ => return None,
+	};
+
+	Some((format!("{{{}}}", body), end + 1))
+}
+
+/**
+Translates POSIX extended regular expression `pattern` into regex crate syntax.
+
+Returns a description of the problem if pattern uses something that has no equivalent (back-references, multi-character collating elements), or is malformed in a way that is not worked around (e.g. unterminated bracket expressions).
+*/
+pub fn translate(pattern: &str) -> Result<String, String> {
+	let chars: Vec<char> = pattern.chars().collect();
+	let mut out = String::with_capacity(pattern.len());
+
+	// where the last thing that a repetition operator might apply to starts in the output
+	let mut last_atom: Option<usize> = None;
+	let mut last_was_repetition = false;
+	// positions of open groups in the output
+	let mut groups: Vec<usize> = vec![];
+
+	let mut i = 0;
+	while i < chars.len() {
+		let c = chars[i];
+		i += 1;
+
+		// repetition operators first: these behave differently depending on what precedes them
+		let repetition = match c {
+			'*' | '+' | '?' => Some((c.to_string(), 0)),
+			'{' => interval(&chars[i ..]),
+			_ => None,
+		};
+		if let Some((op, len)) = repetition {
+			match last_atom {
+				// nothing to repeat, so that's just a literal
+				None => {
+					push_literal(&mut out, c);
+					last_atom = None;
+					last_was_repetition = false;
+					continue;
+				},
+				Some(start) => {
+					if last_was_repetition {
+						out.insert_str(start, "(?:");
+						out.push(')');
+					}
+					out.push_str(&op);
+					i += len;
+					last_was_repetition = true;
+					continue;
+				},
+			}
+		}
+		last_was_repetition = false;
+
+		let start = out.len();
+		match c {
+			'\\' => {
+				let escaped = *chars.get(i).ok_or_else(|| "trailing backslash".to_string())?;
+				i += 1;
+				match escaped {
+					'<' => { out.push_str(r"\b{start}"); last_atom = None; continue; },
+					'>' => { out.push_str(r"\b{end}"); last_atom = None; continue; },
+					'b' | 'B' => { out.push('\\'); out.push(escaped); last_atom = None; continue; },
+					'w' | 'W' | 's' | 'S' => { out.push('\\'); out.push(escaped); },
+					'1' ..= '9' => return Err("back-references are not supported".to_string()),
+					// GNU libc treats other escaped characters as literals
+					_ => push_literal(&mut out, escaped),
+				}
+			},
+			'[' => {
+				i += bracket(&chars[i ..], &mut out)?;
+			},
+			'(' => {
+				groups.push(out.len());
+				out.push_str("(?:");
+				last_atom = None;
+				continue;
+			},
+			')' => match groups.pop() {
+				Some(group) => {
+					out.push(')');
+					last_atom = Some(group);
+					continue;
+				},
+				// unmatched `)` is a literal
+				None => push_literal(&mut out, c),
+			},
+			'|' | '^' | '$' => {
+				out.push(c);
+				last_atom = None;
+				continue;
+			},
+			'.' => out.push('.'),
+			_ => push_literal(&mut out, c),
+		}
+		last_atom = Some(start);
+	}
+
+	if !groups.is_empty() {
+		return Err("unmatched parenthesis".to_string());
+	}
+
+	Ok(out)
+}
+
+// whether there's `|` outside of any group or bracket expression, meaning that the pattern has more than one branch to match the whole string
+fn top_level_alternation(chars: &[char]) -> bool {
+	let mut depth = 0;
+	let mut i = 0;
+	while i < chars.len() {
+		match chars[i] {
+			'\\' => i += 1,
+			'[' => match bracket(&chars[i + 1 ..], &mut String::new()) {
+				Ok(len) => i += len,
+				// let the caller be pessimistic about it
+				Err(_) => return true,
+			},
+			'(' => depth += 1,
+			')' if depth > 0 => depth -= 1,
+			'|' if depth == 0 => return true,
+			_ => (),
+		}
+		i += 1;
+	}
+	false
+}
+
+/**
+Returns a literal string that every string matched by POSIX extended regular expression `pattern` (as a whole, see [`anchored()`](fn.anchored.html)) starts with.
+
+This is a cheap way to rule out patterns that cannot possibly match before compiling them. Returned prefix is not necessarily the longest possible one, and might be empty.
+
+```
+use hdd::drivedb::ere::literal_prefix;
+
+assert_eq!(literal_prefix("ST3[0-9]+AS"), "ST3");
+assert_eq!(literal_prefix(r"WDC WD5000AAKS-0\..*"), "WDC WD5000AAKS-0.");
+assert_eq!(literal_prefix(r"WDC WD5000AAKS-0\.*"), "WDC WD5000AAKS-0");
+// optional characters are not the part of the prefix
+assert_eq!(literal_prefix("SAMSUNG HD?1"), "SAMSUNG H");
+assert_eq!(literal_prefix("Maxtor|MAXTOR"), "");
+assert_eq!(literal_prefix("(Hitachi )?HDS"), "");
+// `a+?` matches empty string, too
+assert_eq!(literal_prefix("a+?"), "");
+assert_eq!(literal_prefix("SSD+"), "SS");
+```
+*/
+pub fn literal_prefix(pattern: &str) -> String {
+	prefix(pattern).0
+}
+
+/**
+Returns the only string that POSIX extended regular expression `pattern` matches (as a whole, see [`anchored()`](fn.anchored.html)), if that's the case.
+
+```
+use hdd::drivedb::ere::literal;
+
+assert_eq!(literal(r"ST3500418AS"), Some("ST3500418AS".to_string()));
+assert_eq!(literal(r"CC3[45]"), None);
+```
+*/
+pub fn literal(pattern: &str) -> Option<String> {
+	match prefix(pattern) {
+		(prefix, true) => Some(prefix),
+		(_, false) => None,
+	}
+}
+
+// literal prefix of the pattern, and whether it is the whole pattern
+fn prefix(pattern: &str) -> (String, bool) {
+	let chars: Vec<char> = pattern.chars().collect();
+	if top_level_alternation(&chars) {
+		return (String::new(), false);
+	}
+
+	let mut prefix = String::new();
+	let mut i = 0;
+	while i < chars.len() {
+		let c = match chars[i] {
+			// only escaped punctuation is surely a literal, escaped letters mean all sorts of things
+			'\\' => match chars.get(i + 1) {
+				Some(&c) if c.is_ascii_punctuation() && c != '<' && c != '>' => { i += 1; c },
+				_ => break,
+			},
+			'.' | '[' | '(' | ')' | '|' | '*' | '+' | '?' | '{' | '^' | '$' => break,
+			c => c,
+		};
+		i += 1;
+		// repetition makes the character optional (or, in case of a valid `{…}`, it might), so it's not a part of the prefix;
+		// so does `+` if followed by other repetition operators (`a+?`), hence no `+` either
+		if matches!(chars.get(i), Some('*' | '?' | '{' | '+')) {
+			break;
+		}
+		prefix.push(c);
+	}
+	(prefix, i >= chars.len())
+}
+
+/// Translates `pattern` just like [`translate()`](fn.translate.html) does, and anchors it at both ends, as drivedb patterns should match the whole string.
+pub fn anchored(pattern: &str) -> Result<String, String> {
+	translate(pattern).map(|p| format!("^(?:{})$", p))
+}
@@ -1,11 +1,12 @@
 use super::parser::{self, Entry};
+use super::{cache, preprocessor};
 use super::DriveDB;
 
 use std::fs::File;
 use std::io::prelude::*;
 use std::io;
+use std::path::{Path, PathBuf};
 
-use regex; // for Loader.db() error type
 
 quick_error! {
 	#[derive(Debug)]
@@ -14,21 +15,65 @@ quick_error! {
 			from()
 			display("IO error: {}", err)
 		}
-		Parse {
-			// TODO? Parse(nom::verbose_errors::Err) if dependencies.nom.features = ["verbose-errors"]
-			display("Unable to parse the drivedb")
+		Parse(line: usize, column: usize, reason: String) {
+			display("Unable to parse the drivedb at line {}, column {}: {}", line, column, reason)
 		}
 	}
 }
 
-fn load(file: &str) -> Result<Vec<Entry>, Error> {
+// converts byte offset into 1-based line and column numbers
+fn position(text: &[u8], offset: usize) -> (usize, usize) {
+	let before = &text[.. offset];
+	let line_start = before.iter().rposition(|&c| c == b'\n').map(|i| i + 1).unwrap_or(0);
+	let line = before.iter().filter(|&&c| c == b'\n').count() + 1;
+	// count characters rather than bytes; the line itself is valid UTF-8 up to the offset, unless offset points at the encoding error
+	let column = String::from_utf8_lossy(&before[line_start ..]).chars().count() + 1;
+	(line, column)
+}
+
+fn load(file: &str, cache_dir: Option<&Path>) -> Result<Vec<Entry>, Error> {
 	let mut db = Vec::new();
 	File::open(&file)?.read_to_end(&mut db)?;
 
-	let db = ::std::str::from_utf8(&db).map_err(|_| Error::Parse)?;
-	match parser::database(db) {
+	let key = cache::key(&db);
+	let cached = cache_dir.and_then(|dir| cache::read(dir, key));
+
+	let mut entries = match cached {
+		Some(entries) => entries,
+		None => {
+			let entries = parse(&db)?;
+			if let Some(dir) = cache_dir
+				&& let Err(e) = cache::write(dir, key, &entries)
+			{
+				warn!("cannot write drivedb cache into {}: {}", dir.display(), e);
+			}
+			entries
+		},
+	};
+
+	for entry in entries.iter_mut() {
+		entry.file = Some(file.to_string());
+	}
+	Ok(entries)
+}
+
+fn parse(db: &[u8]) -> Result<Vec<Entry>, Error> {
+	let text = ::std::str::from_utf8(db).map_err(|e| {
+		let (line, column) = position(db, e.valid_up_to());
+		Error::Parse(line, column, "invalid UTF-8".to_string())
+	})?;
+
+	// preprocessor keeps lines in place, so positions in its output are the same as the ones in the file
+	let text = preprocessor::preprocess(text)
+		.map_err(|e| Error::Parse(e.line, 1, e.message))?;
+
+	match parser::database(&text) {
 		Ok((_, entries)) => Ok(entries),
-		Err(_) => Err(Error::Parse),
+		Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+			let (line, column) = position(text.as_bytes(), text.len() - e.input.len());
+			Err(Error::Parse(line, column, "malformed drive entry".to_string()))
+		},
+		Err(nom::Err::Incomplete(_)) => unreachable!(), // complete parsers only
 	}
 }
 
@@ -41,15 +86,25 @@ It is also possible to use `Loader` to create dummy database in case if only use
 pub struct Loader {
 	entries: Vec<Entry>,
 	additional: Vec<Entry>,
+	cache_dir: Option<PathBuf>,
 }
 impl Loader {
 	pub fn new() -> Self {
 		Loader {
 			entries: vec![],
 			additional: vec![],
+			cache_dir: None,
 		}
 	}
 	/**
+	Makes loader keep parsed drivedb files in `dir` and reuse them later, which is useful for short-lived processes that would otherwise parse the same files over and over again.
+
+	Cache files are looked up by the hash of drivedb file contents, so that updated files are always parsed anew. Problems with cache are never fatal: loader falls back to parsing, and only logs a warning.
+	*/
+	pub fn use_cache(&mut self, dir: &str) {
+		self.cache_dir = Some(PathBuf::from(dir));
+	}
+	/**
 	Loads entries from main drivedb file.
 
 	Entries from previously loaded main file will be discarded; entries from additional files will not be affected.
@@ -62,10 +117,22 @@ impl Loader {
 	- drive database is malformed.
 	*/
 	pub fn load(&mut self, file: &str) -> Result<(), Error> {
-		self.entries = load(file)?;
+		self.entries = load(file, self.cache_dir.as_deref())?;
 		Ok(())
 	}
 	/**
+	Loads entries from drivedb snapshot that was embedded into the crate at build time, using them as the main file (see [`load()`](#method.load)).
+
+	This is handy as a fallback for the systems without smartmontools installed, although the snapshot is likely to be outdated. Snapshot is empty if there was none to embed at build time.
+	*/
+	#[cfg(feature = "embedded-drivedb")]
+	pub fn load_builtin(&mut self) {
+		self.entries = super::builtin::entries();
+		if self.entries.is_empty() {
+			warn!("built-in drivedb is empty: no snapshot was available at build time");
+		}
+	}
+	/**
 	Loads more entries from additional drivedb file. Additional entries always take precedence over the ones from the main file.
 
 	## Errors
@@ -76,11 +143,11 @@ impl Loader {
 	- drive database is malformed.
 	*/
 	pub fn load_additional(&mut self, file: &str) -> Result<(), Error> {
-		self.entries = load(file)?;
+		self.additional.extend(load(file, self.cache_dir.as_deref())?);
 		Ok(())
 	}
 	/// Returns actual drive database with all entries loaded beforehand.
-	pub fn db(self) -> Result<DriveDB, regex::Error> {
+	pub fn db(self) -> DriveDB {
 		let entries: Vec<_> = self.additional.into_iter()
 			.chain(self.entries.into_iter())
 			.collect();
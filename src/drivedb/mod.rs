@@ -14,7 +14,7 @@ loader.load("/var/lib/smartmontools/drivedb/drivedb.h")
 	.or_else(|_| loader.load("/usr/share/smartmontools/drivedb.h"))?;
 // `?` is optional though: if nothing can be loaded, loader will still provide dummy db for us
 
-let db = loader.db()?;
+let db = loader.db();
 
 // extra attribute definitions that user might give
 let user_attributes = vec!["9,minutes"]
@@ -27,7 +27,7 @@ let id: id::Id = unimplemented!();
 
 let meta = db.render_meta(&id, &user_attributes);
 
-if let Some(warn) = meta.warning {
+if let Some(warn) = &meta.warning {
 	println!("WARNING: {}", warn);
 }
 
@@ -41,11 +41,17 @@ if let Some(attr) = meta.render_attribute(9) {
 ```
 */
 
+pub mod preprocessor;
 mod parser;
 mod presets;
 mod drivedb;
+pub mod ere;
 mod loader;
+mod cache;
+#[cfg(feature = "embedded-drivedb")]
+mod builtin;
 pub mod vendor_attribute;
 pub use self::vendor_attribute::Attribute;
-pub use self::drivedb::{DriveDB, DriveMeta};
+pub use self::drivedb::{DriveDB, SharedDriveDB, DriveMeta, EntryMatch, InvalidEntry, Issue};
+pub use self::presets::{FirmwareBug, LogFormat, LogParameter};
 pub use self::loader::{Loader, Error};
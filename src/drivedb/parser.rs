@@ -1,7 +1,7 @@
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_until};
 use nom::character::complete::{char, multispace1, none_of, one_of};
-use nom::combinator::{eof, map, opt, value};
+use nom::combinator::{cut, eof, map, opt, value};
 use nom::multi::{many0, many1};
 use nom::sequence::{delimited, preceded, terminated};
 use nom::IResult;
@@ -86,6 +86,11 @@ pub struct Entry {
 	/// > String with vendor-specific attribute ('-v') and firmware bug fix ('-F') options.
 	/// > Same syntax as in smartctl command line.
 	pub presets: String,
+
+	/// file this entry was loaded from, if any
+	pub file: Option<String>,
+	/// line where this entry starts
+	pub line: usize,
 }
 
 fn comma(input: &str) -> IResult<&str, ()> {
@@ -97,6 +102,11 @@ fn comma(input: &str) -> IResult<&str, ()> {
 
 fn entry(input: &str) -> IResult<&str, Entry> {
 	let (input, _) = char('{')(input)?;
+	// past the opening brace, this can only be an entry, so there's no point in backtracking: the error should point at whatever is wrong inside
+	cut(entry_body).parse(input)
+}
+
+fn entry_body(input: &str) -> IResult<&str, Entry> {
 	let (input, _) = whitespace(input)?;
 	let (input, family) = string(input)?;
 	let (input, _) = comma(input)?;
@@ -117,16 +127,37 @@ fn entry(input: &str) -> IResult<&str, Entry> {
 			firmware: firmware,
 			warning: warning,
 			presets: presets,
+			file: None,
+			line: 0,
 		},
 	))
 }
 
+// entry along with the length of the input that is left before it, to find out where entry starts later on
+fn entry_located(input: &str) -> IResult<&str, (usize, Entry)> {
+	let left = input.len();
+	let (input, entry) = entry(input)?;
+	Ok((input, (left, entry)))
+}
+
 pub fn database(input: &str) -> IResult<&str, Vec<Entry>> {
+	let db = input;
 	let (input, _) = whitespace(input)?;
-	let (input, entries) = many1(terminated(entry, comma)).parse(input)?;
+	let (input, entries) = many1(terminated(entry_located, comma)).parse(input)?;
 	let (input, _) = whitespace(input)?;
 	let (input, _) = eof(input)?;
+
+	// entries go in order, so there's no need to count newlines from the very beginning every time
+	let mut line = 1;
+	let mut offset = 0;
 	let entries = entries.into_iter()
+		.map(|(left, mut entry)| {
+			let start = db.len() - left;
+			line += db[offset .. start].matches('\n').count();
+			offset = start;
+			entry.line = line;
+			entry
+		})
 		.filter(|entry| {
 			// > The entry is ignored if [modelfamily] starts with a dollar sign.
 			!entry.family.starts_with('$')
@@ -0,0 +1,478 @@
+/*!
+Just enough of C preprocessor to deal with drivedb files shipped with smartmontools.
+
+`drivedb.h` is, after all, a piece of C++ source meant to be `#include`d, so it is allowed to have conditional sections (`#if`, `#ifdef` and friends), string macros (`#define`), and other directives. This module evaluates all of that, producing plain text that [parser](../parser/index.html) can deal with.
+
+Every line of the input yields exactly one line of the output (directives and inactive sections turn into empty lines), so that line numbers of the output match the ones of the input.
+
+## Example
+
+```
+use hdd::drivedb::preprocessor::preprocess;
+
+let input = [
+	r#"#define VENDOR "WDC ""#,
+	r#"#define MODEL VENDOR "WD5000AAKS""#,
+	r#"#if defined(FOO) || 0"#,
+	r#""skipped""#,
+	r#"#elif !defined FOO && 1"#,
+	r#"#ifdef FOO"#,
+	r#""skipped too""#,
+	r#"#else"#,
+	r#"MODEL /* comment that hides"#,
+	r#"#endif"#,
+	r#"*/"#,
+	r#"#endif"#,
+	r#"#else"#,
+	r#""skipped as well""#,
+	r#"#endif /* comment that starts on a directive line"#,
+	r#"#if 0"#,
+	r#"*/ "done""#,
+].join("\n");
+
+let output = preprocess(&input).unwrap();
+let output: Vec<_> = output.split('\n').collect();
+assert_eq!(output.len(), 17);
+assert!(output[.. 8].iter().all(|line| line.is_empty()));
+assert_eq!(output[8], r#""WDC " "WD5000AAKS" /* comment that hides"#);
+assert_eq!(output[9], "#endif");
+assert_eq!(output[10], "*/");
+assert!(output[11 .. 16].iter().all(|line| line.is_empty()));
+assert_eq!(output[16], r#" "done""#);
+
+// errors point at the offending line
+assert_eq!(preprocess("#if 1\n#else\n#else\n#endif").unwrap_err().line, 3);
+assert_eq!(preprocess("\n#if 1\n\"text\"\n").unwrap_err().line, 2);
+assert_eq!(preprocess("#if 0\n#error skipped\n#else\n#error not skipped\n#endif").unwrap_err().line, 4);
+```
+*/
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Error {
+	pub line: usize,
+	pub message: String,
+}
+
+impl Error {
+	fn new(line: usize, message: &str) -> Self {
+		Error { line, message: message.to_string() }
+	}
+}
+
+// state of a single `#if`…`#endif` block
+struct Conditional {
+	// line of the opening directive, for error reporting
+	line: usize,
+	// whether the enclosing block is active
+	parent_active: bool,
+	// whether the current branch is active
+	active: bool,
+	// whether any of the branches was taken already, meaning that the remaining ones are not to be taken
+	taken: bool,
+	else_seen: bool,
+}
+
+fn is_ident_start(c: char) -> bool { c.is_ascii_alphabetic() || c == '_' }
+fn is_ident(c: char) -> bool { c.is_ascii_alphanumeric() || c == '_' }
+
+// splits identifier off the start of the string
+fn ident(s: &str) -> Option<(&str, &str)> {
+	if !s.starts_with(is_ident_start) { return None; }
+	let end = s.find(|c| !is_ident(c)).unwrap_or(s.len());
+	Some(s.split_at(end))
+}
+
+/*
+Substitutes macros in a single line of source.
+
+`in_comment` carries the state of block comments between lines. String literals and comments are copied as is.
+*/
+fn expand(line: &str, defines: &HashMap<String, String>, in_comment: &mut bool) -> String {
+	let mut out = String::with_capacity(line.len());
+	let mut rest = line;
+
+	while !rest.is_empty() {
+		if *in_comment {
+			match rest.find("*/") {
+				Some(end) => {
+					out.push_str(&rest[.. end + 2]);
+					rest = &rest[end + 2 ..];
+					*in_comment = false;
+				},
+				None => {
+					out.push_str(rest);
+					rest = "";
+				},
+			}
+		} else if rest.starts_with("/*") {
+			out.push_str("/*");
+			rest = &rest[2 ..];
+			*in_comment = true;
+		} else if rest.starts_with("//") {
+			out.push_str(rest);
+			rest = "";
+		} else if rest.starts_with('"') {
+			// find the closing quote, skipping escaped characters; unterminated literals are left for the parser to complain about
+			let mut end = rest.len();
+			let mut escaped = false;
+			for (i, c) in rest.char_indices().skip(1) {
+				match c {
+					_ if escaped => escaped = false,
+					'\\' => escaped = true,
+					'"' => { end = i + 1; break; },
+					_ => (),
+				}
+			}
+			out.push_str(&rest[.. end]);
+			rest = &rest[end ..];
+		} else if let Some((name, tail)) = ident(rest) {
+			match defines.get(name) {
+				Some(body) => out.push_str(body),
+				None => out.push_str(name),
+			}
+			rest = tail;
+		} else {
+			let c = rest.chars().next().unwrap();
+			out.push(c);
+			rest = &rest[c.len_utf8() ..];
+		}
+	}
+
+	out
+}
+
+/*
+Cuts comments off the directive, which might be followed by them.
+
+Block comment that is not closed on the same line goes on to the following ones, hence `in_comment`.
+*/
+fn strip_comment<'a>(directive: &'a str, in_comment: &mut bool) -> &'a str {
+	let mut in_string = false;
+	let mut escaped = false;
+	for (i, c) in directive.char_indices() {
+		if in_string {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' => escaped = true,
+				'"' => in_string = false,
+				_ => (),
+			}
+			continue;
+		}
+		let rest = &directive[i ..];
+		if c == '"' {
+			in_string = true;
+		} else if rest.starts_with("//") {
+			return &directive[.. i];
+		} else if rest.starts_with("/*") {
+			// only to keep track of the comment state, there's nothing to expand there
+			expand(rest, &HashMap::new(), in_comment);
+			return &directive[.. i];
+		}
+	}
+	directive
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Number(i64),
+	Ident(String),
+	Op(&'static str),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+	// longer operators go first
+	const OPS: [&str; 12] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", "-"];
+
+	let mut tokens = vec![];
+	let mut rest = expr.trim_start();
+	'outer: while !rest.is_empty() {
+		if let Some((name, tail)) = ident(rest) {
+			tokens.push(Token::Ident(name.to_string()));
+			rest = tail;
+		} else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+			let end = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+			// C integer suffixes (`1L`, `0x10u`) don't matter here
+			let number = rest[.. end].trim_end_matches(['u', 'U', 'l', 'L']);
+			let number = match number.strip_prefix("0x").or_else(|| number.strip_prefix("0X")) {
+				Some(hex) => i64::from_str_radix(hex, 16),
+				None => number.parse::<i64>(),
+			}.map_err(|_| format!("invalid number {}", &rest[.. end]))?;
+			tokens.push(Token::Number(number));
+			rest = &rest[end ..];
+		} else {
+			for op in OPS.iter() {
+				if let Some(tail) = rest.strip_prefix(op) {
+					tokens.push(Token::Op(op));
+					rest = tail;
+					rest = rest.trim_start();
+					continue 'outer;
+				}
+			}
+			return Err(format!("unexpected character in expression: {}", rest));
+		}
+		rest = rest.trim_start();
+	}
+
+	Ok(tokens)
+}
+
+// recursive descent evaluator for `#if` expressions, with the usual C precedence of supported operators
+struct Expr<'a> {
+	tokens: &'a [Token],
+	pos: usize,
+	defines: &'a HashMap<String, String>,
+}
+
+impl<'a> Expr<'a> {
+	fn peek_op(&self, op: &str) -> bool {
+		matches!(self.tokens.get(self.pos), Some(Token::Op(x)) if *x == op)
+	}
+
+	fn expect_op(&mut self, op: &str) -> Result<(), String> {
+		if !self.peek_op(op) {
+			return Err(format!("expected '{}' in expression", op));
+		}
+		self.pos += 1;
+		Ok(())
+	}
+
+	fn or(&mut self) -> Result<i64, String> {
+		let mut value = self.and()?;
+		while self.peek_op("||") {
+			self.pos += 1;
+			let rhs = self.and()?;
+			value = (value != 0 || rhs != 0) as i64;
+		}
+		Ok(value)
+	}
+
+	fn and(&mut self) -> Result<i64, String> {
+		let mut value = self.comparison()?;
+		while self.peek_op("&&") {
+			self.pos += 1;
+			let rhs = self.comparison()?;
+			value = (value != 0 && rhs != 0) as i64;
+		}
+		Ok(value)
+	}
+
+	fn comparison(&mut self) -> Result<i64, String> {
+		let mut value = self.unary()?;
+		loop {
+			let op = match self.tokens.get(self.pos) {
+				Some(Token::Op(op)) if ["==", "!=", "<", ">", "<=", ">="].contains(op) => *op,
+				_ => return Ok(value),
+			};
+			self.pos += 1;
+			let rhs = self.unary()?;
+			value = match op {
+				"==" => value == rhs,
+				"!=" => value != rhs,
+				"<" => value < rhs,
+				">" => value > rhs,
+				"<=" => value <= rhs,
+				">=" => value >= rhs,
+				_ => unreachable!(),
+			} as i64;
+		}
+	}
+
+	fn unary(&mut self) -> Result<i64, String> {
+		let token = self.tokens.get(self.pos).cloned()
+			.ok_or_else(|| "unexpected end of expression".to_string())?;
+		self.pos += 1;
+
+		match token {
+			Token::Op("!") => Ok((self.unary()? == 0) as i64),
+			Token::Op("-") => Ok(-self.unary()?),
+			Token::Op("(") => {
+				let value = self.or()?;
+				self.expect_op(")")?;
+				Ok(value)
+			},
+			Token::Number(x) => Ok(x),
+			// both `defined(NAME)` and `defined NAME` are valid
+			Token::Ident(ref name) if name == "defined" => {
+				let parens = self.peek_op("(");
+				if parens { self.pos += 1; }
+				let value = match self.tokens.get(self.pos) {
+					Some(Token::Ident(name)) => self.defines.contains_key(name) as i64,
+					_ => return Err("expected macro name after 'defined'".to_string()),
+				};
+				self.pos += 1;
+				if parens { self.expect_op(")")?; }
+				Ok(value)
+			},
+			// as in C, identifiers that are not macros evaluate to 0
+			Token::Ident(name) => match self.defines.get(&name) {
+				Some(body) => {
+					let tokens = tokenize(body)?;
+					let mut expr = Expr { tokens: &tokens, pos: 0, defines: self.defines };
+					expr.or()
+				},
+				None => Ok(0),
+			},
+			Token::Op(op) => Err(format!("unexpected '{}' in expression", op)),
+		}
+	}
+}
+
+fn evaluate(expr: &str, defines: &HashMap<String, String>) -> Result<bool, String> {
+	let tokens = tokenize(expr)?;
+	let mut e = Expr { tokens: &tokens, pos: 0, defines };
+	let value = e.or()?;
+	if e.pos != tokens.len() {
+		return Err("unexpected tokens at the end of expression".to_string());
+	}
+	Ok(value != 0)
+}
+
+/**
+Evaluates preprocessor directives of `input`, returning the text that is left.
+
+Supported are conditionals (`#if`, `#ifdef`, `#ifndef`, `#elif`, `#else`, `#endif`; expressions might use integers, `defined`, `!`, `&&`, `||` and comparisons) and object-like macros (`#define`, `#undef`) that are expanded in the rest of the text, which is how string literals might be glued from several macros. `#include`, `#pragma`, `#line` and `#warning` are ignored: there's nothing of interest for us in C headers drivedb might want to include. `#error` in an active section is an error, and so are unknown directives.
+*/
+pub fn preprocess(input: &str) -> Result<String, Error> {
+	let mut defines: HashMap<String, String> = HashMap::new();
+	let mut stack: Vec<Conditional> = vec![];
+	let mut in_comment = false;
+	// whether the comment that `in_comment` is about was opened by a directive
+	let mut directive_comment = false;
+	let mut out = String::with_capacity(input.len());
+
+	let mut lines = input.split('\n').enumerate().map(|(i, line)| (i + 1, line));
+	while let Some((n, line)) = lines.next() {
+		let active = stack.last().map(|c| c.active).unwrap_or(true);
+
+		let directive = if in_comment { None } else { line.trim_start().strip_prefix('#') };
+		let directive = match directive {
+			None => {
+				let line = if directive_comment {
+					match line.find("*/") {
+						Some(end) => {
+							in_comment = false;
+							directive_comment = false;
+							&line[end + 2 ..]
+						},
+						None => "",
+					}
+				} else { line };
+				let expanded = expand(line, &defines, &mut in_comment);
+				// inactive lines still need to be expanded to keep track of comments
+				if active {
+					out.push_str(&expanded);
+				}
+				out.push('\n');
+				continue;
+			},
+			Some(directive) => directive,
+		};
+
+		// join continuation lines, keeping line count intact
+		let mut directive = directive.to_string();
+		while directive.ends_with('\\') {
+			directive.pop();
+			match lines.next() {
+				Some((_, next)) => {
+					directive.push(' ');
+					directive.push_str(next);
+					out.push('\n');
+				},
+				None => break,
+			}
+		}
+		// comments are allowed after directives
+		let directive = strip_comment(&directive, &mut in_comment).trim();
+		// directives are not copied to the output, and so is the comment that follows, even if it goes on for a few more lines
+		directive_comment = in_comment;
+		out.push('\n');
+
+		let (name, arg) = match ident(directive) {
+			Some((name, arg)) => (name, arg.trim()),
+			// null directive
+			None if directive.is_empty() => continue,
+			None => return Err(Error::new(n, "invalid preprocessor directive")),
+		};
+
+		match name {
+			"if" | "ifdef" | "ifndef" => {
+				let cond = if !active {
+					// no need to evaluate anything, and it might be invalid anyway
+					false
+				} else {
+					match name {
+						"ifdef" => defines.contains_key(arg),
+						"ifndef" => !defines.contains_key(arg),
+						_ => evaluate(arg, &defines).map_err(|e| Error { line: n, message: e })?,
+					}
+				};
+				stack.push(Conditional {
+					line: n,
+					parent_active: active,
+					active: cond,
+					taken: cond,
+					else_seen: false,
+				});
+			},
+			"elif" => {
+				let c = stack.last_mut().ok_or_else(|| Error::new(n, "#elif without #if"))?;
+				if c.else_seen {
+					return Err(Error::new(n, "#elif after #else"));
+				}
+				c.active = if c.parent_active && !c.taken {
+					evaluate(arg, &defines).map_err(|e| Error { line: n, message: e })?
+				} else { false };
+				c.taken |= c.active;
+			},
+			"else" => {
+				let c = stack.last_mut().ok_or_else(|| Error::new(n, "#else without #if"))?;
+				if c.else_seen {
+					return Err(Error::new(n, "#else after #else"));
+				}
+				c.else_seen = true;
+				c.active = c.parent_active && !c.taken;
+				c.taken = true;
+			},
+			"endif" => {
+				stack.pop().ok_or_else(|| Error::new(n, "#endif without #if"))?;
+			},
+
+			// everything below only matters in active sections
+			_ if !active => (),
+
+			"define" => {
+				let (macro_name, body) = ident(arg)
+					.ok_or_else(|| Error::new(n, "macro name expected after #define"))?;
+				if body.starts_with('(') {
+					warn!("drivedb line {}: function-like macro {} is not supported, ignoring", n, macro_name);
+				} else {
+					// expanding the body right away takes care of macros that refer to other macros
+					let body = expand(body.trim(), &defines, &mut false);
+					defines.insert(macro_name.to_string(), body);
+				}
+			},
+			"undef" => {
+				defines.remove(arg);
+			},
+			"include" | "pragma" | "line" | "warning" => {
+				debug!("drivedb line {}: ignoring #{} {}", n, name, arg);
+			},
+			"error" => return Err(Error { line: n, message: format!("#error {}", arg) }),
+			_ => return Err(Error { line: n, message: format!("unknown preprocessor directive #{}", name) }),
+		}
+	}
+
+	if let Some(c) = stack.last() {
+		return Err(Error::new(c.line, "#if without #endif"));
+	}
+
+	// every line got a newline appended, including the last one, which might not have it in the input
+	if !input.ends_with('\n') {
+		out.pop();
+	}
+
+	Ok(out)
+}
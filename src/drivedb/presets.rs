@@ -1,22 +1,126 @@
 use super::vendor_attribute;
 use super::vendor_attribute::Attribute;
 
-pub fn parse(line: &str) -> Option<Vec<Attribute>> {
+/**
+Firmware bugs that drivedb entries (or users, see `-F` in smartctl(8)) can tell about, so that the data that device returns is fixed up accordingly.
+
+Only the bugs that affect data this crate parses are listed here. Other smartctl values (`nologdir`, `samsung`, `samsung2`, `xerrorlba`) concern log directories, self-test and error logs, which are not read yet; they are accepted, but ignored.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum FirmwareBug {
+	/// device keeps reporting the last self-test as in progress with 0% remaining after it has completed (`samsung3`)
+	Samsung3,
+	/// IDENTIFY DEVICE strings (serial number, firmware revision, model) are byte-swapped, usually by some USB bridge (`swapid`)
+	SwapId,
+}
+
+/// How to interpret the value of SCSI log parameter, see [`LogParameter`](struct.LogParameter.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum LogFormat {
+	/// big-endian unsigned integer (`uint`)
+	Uint,
+	/// ASCII string (`ascii`)
+	ASCII,
+	/// raw bytes, shown in hex (`hex`)
+	Hex,
+}
+
+/**
+Description of a (usually vendor-specific) SCSI log page parameter, given with `-l PAGE,PARAM,FORMAT[,NAME]` in presets of SCSI drivedb entries.
+
+`PAGE` and `PARAM` are either decimal or `0x`-prefixed hexadecimal numbers; `FORMAT` is one of `uint`, `ascii` and `hex` (see [`LogFormat`](enum.LogFormat.html)). This option is specific to this crate, smartctl(8) knows nothing about it.
+*/
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct LogParameter {
+	pub page: u8,
+	/// parameter code
+	pub code: u16,
+	pub format: LogFormat,
+	pub name: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Presets {
+	pub attributes: Vec<Attribute>,
+	pub firmware_bugs: Vec<FirmwareBug>,
+	pub log_parameters: Vec<LogParameter>,
+	/// options that cannot be parsed or are not supported, as they appear in the preset string
+	pub invalid: Vec<String>,
+}
+
+fn parse_firmware_bug(s: &str) -> Option<FirmwareBug> {
+	use self::FirmwareBug::*;
+	Some(match s {
+		"samsung3" => Samsung3,
+		"swapid" => SwapId,
+		_ => return None,
+	})
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+	match s.strip_prefix("0x") {
+		Some(hex) => u64::from_str_radix(hex, 16).ok(),
+		None => s.parse().ok(),
+	}
+}
+
+fn parse_log_parameter(s: &str) -> Option<LogParameter> {
+	let mut fields = s.splitn(4, ',');
+	let page = fields.next().and_then(parse_number)?;
+	let code = fields.next().and_then(parse_number)?;
+	let format = match fields.next()? {
+		"uint" => LogFormat::Uint,
+		"ascii" => LogFormat::ASCII,
+		"hex" => LogFormat::Hex,
+		_ => return None,
+	};
+	Some(LogParameter {
+		page: u8::try_from(page).ok()?,
+		code: u16::try_from(code).ok()?,
+		format,
+		name: fields.next().map(|name| name.to_string()),
+	})
+}
+
+/// Parses preset string; options that cannot be applied are skipped, and are listed in `invalid` field of the result.
+pub fn parse(line: &str) -> Presets {
 	// using clap here would be an overkill
-	let mut args = line.split_whitespace().into_iter();
-	let mut output = Vec::<Attribute>::new();
+	let mut args = line.split_whitespace();
+	let mut output = Presets::default();
 	loop {
 		match args.next() {
-			None => return Some(output),
+			None => return output,
 			Some(key) => match args.next() {
-				None => return None, // we always expect an argument for the option
+				// we always expect an argument for the option
+				None => {
+					output.invalid.push(key.to_string());
+					return output;
+				},
 				Some(value) => {
 					match key {
 						"-v" => { match vendor_attribute::parse(value) {
-							Ok(attr) => output.push(attr),
-							Err(_) => (), // TODO
+							Ok(attr) => output.attributes.push(attr),
+							Err(_) => output.invalid.push(format!("{} {}", key, value)),
 						} },
-						_ => continue, // TODO other options
+						// smartctl accepts comma-separated lists here
+						"-F" => for bug in value.split(',') {
+							match (bug, parse_firmware_bug(bug)) {
+								// as in smartmontools, this discards bugs listed before
+								("none", _) => output.firmware_bugs.clear(),
+								// valid, but only concern log directories, self-test and error logs, which this crate does not read
+								("nologdir", _) | ("samsung", _) | ("samsung2", _) | ("xerrorlba", _) => (),
+								(_, Some(bug)) => output.firmware_bugs.push(bug),
+								(_, None) => output.invalid.push(format!("{} {}", key, bug)),
+							}
+						},
+						"-l" => match parse_log_parameter(value) {
+							Some(param) => output.log_parameters.push(param),
+							None => output.invalid.push(format!("{} {}", key, value)),
+						},
+						_ => output.invalid.push(format!("{} {}", key, value)),
 					}
 				},
 			},
@@ -28,10 +28,12 @@ quick_error! {
 
 /// HDD or SSD
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
 pub enum Type { HDD, SSD }
 
 /// SMART attribute description
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
 pub struct Attribute {
 	/// id of described attribute
 	pub id: Option<u8>,
@@ -41,10 +43,28 @@ pub struct Attribute {
 	pub format: String,
 	/// bytes of attribute data to make value of (usually something like `r543210`, where `r`, `v`, `w` represent reserved byte, current and worst values respectively)
 	pub byte_order: String,
+	/// raw value is a counter that is never reset (e.g. by off-line data collection); set with `+` after the format name (smartmontools: `ATTRFLAG_INCREASING`)
+	pub increasing: bool,
 	/// what kind of device this description is applicable to: HDD, SSD, or both
 	pub drivetype: Option<Type>,
 }
 
+impl Attribute {
+	/// Whether normalized value is not valid because its byte is used to render the raw value (smartmontools: `ATTRFLAG_NO_NORMVAL`).
+	pub fn no_normval(&self) -> bool {
+		self.byte_order.contains('v')
+	}
+	/// Whether worst value is not valid. As in smartmontools (`ATTRFLAG_NO_WORSTVAL`), that's also the case if normalized value is used in the raw one, since worst value is meaningless without it.
+	pub fn no_worstval(&self) -> bool {
+		self.byte_order.contains('v') || self.byte_order.contains('w')
+	}
+}
+
+// byte order is up to 8 of these: raw bytes, reserved byte, normalized and worst value, and zero byte
+fn is_valid_byte_order(byte_order: &str) -> bool {
+	byte_order.len() <= 8 && byte_order.chars().all(|c| "012345rvwz".contains(c))
+}
+
 fn parse_standard(input: &str) -> IResult<&str, Attribute> {
 	let (input, id) = alt((
 		map_res(digit1, |s: &str| s.parse::<u8>().map(Some)),
@@ -52,8 +72,14 @@ fn parse_standard(input: &str) -> IResult<&str, Attribute> {
 	)).parse(input)?;
 	let (input, _) = char(',')(input)?;
 	let (input, format) = take_till1(|c| c == ',' || c == ':')(input)?;
-	// TODO '+' for ATTRFLAG_INCREASING
+	let (format, increasing) = match format.strip_suffix('+') {
+		Some(format) => (format, true),
+		None => (format, false),
+	};
 	let (input, byte_order) = opt(preceded(char(':'), take_till1(|c| c == ','))).parse(input)?;
+	if let Some(byte_order) = byte_order.filter(|b| !is_valid_byte_order(b)) {
+		return Err(nom::Err::Error(nom::error::Error::new(byte_order, nom::error::ErrorKind::Verify)));
+	}
 	let (input, name_drive_type) = opt(preceded(
 		char(','),
 		(
@@ -83,6 +109,7 @@ fn parse_standard(input: &str) -> IResult<&str, Attribute> {
 			name: name.map(|x| x.to_string()),
 			format: format.to_string(),
 			byte_order: byte_order.unwrap_or(default_byte_order).to_string(),
+			increasing: increasing,
 			drivetype: drive_type,
 		},
 	))
@@ -93,7 +120,7 @@ Parses single attribute description (`-v` option argument).
 
 The following formats are supported:
 
-* `ID,FORMAT[:BYTEORDER][,NAME[,(HDD|SSD)]]`
+* `ID,FORMAT[+][:BYTEORDER][,NAME[,(HDD|SSD)]]`
 * legacy `-v` arguments, like `9,halfminutes`
 */
 pub fn parse(s: &str) -> Result<Attribute, Error> {
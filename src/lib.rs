@@ -89,4 +89,6 @@ pub mod scsi;
 
 pub mod drivedb;
 
+pub mod metrics;
+
 mod utils;
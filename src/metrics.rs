@@ -0,0 +1,344 @@
+/*!
+Vendor-neutral health metrics, for dashboards and alerting.
+
+Every vendor reports things like power-on time or temperature in its own way: ATA drives use SMART attributes (with IDs, names and raw value formats that differ from one vendor to another, see [drivedb](../drivedb/index.html)) or Device Statistics log, SCSI drives use various log pages. This module picks those values out of the data that is already parsed elsewhere in this crate, and converts them into a fixed set of [metrics](enum.Kind.html) with fixed units.
+
+Metrics that a device does not report (or that cannot be recognized reliably) are simply omitted.
+
+## Example
+
+```no_run
+use hdd::Device;
+use hdd::ata::ATADevice;
+use hdd::ata::misc::Misc;
+use hdd::scsi::SCSIDevice;
+use hdd::drivedb::Loader;
+use hdd::metrics;
+
+# fn main() -> Result<(), Box<dyn std::error::Error>> {
+let dev = ATADevice::new(SCSIDevice::new(Device::open("/dev/da0")?));
+let id = dev.get_device_id()?;
+
+// attribute names come from drivedb, so it's better to have one
+let db = Loader::new().db();
+let meta = Some(db.render_meta(&id, &vec![]));
+let attrs = dev.get_smart_attributes(&meta)?;
+
+let stats = if id.gp_logging_supported {
+	dev.get_device_statistics().ok()
+} else { None };
+
+for metric in metrics::ata(&attrs, stats.as_ref(), id.sector_size_log) {
+	println!("{} = {} {}", metric.kind.key(), metric.value, metric.kind.unit().name());
+}
+# Ok(())
+# }
+```
+*/
+
+use crate::ata::data::attr::SmartAttribute;
+use crate::ata::data::attr::raw::Raw;
+use crate::ata::data::device_stats::DeviceStatistics;
+use crate::scsi::pages::ErrorCounter;
+
+use std::collections::HashMap;
+
+/// What the metric measures; see [`key()`](#method.key) and [`unit()`](#method.unit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Kind {
+	/// number of sectors that were remapped to the spare area
+	ReallocatedSectors,
+	/// number of unstable sectors waiting to be remapped (or to be successfully rewritten)
+	PendingSectors,
+	PowerOnHours,
+	/// current temperature
+	Temperature,
+	/// estimated percentage of rated endurance that is used up: 0 for a brand new device, 100 for the one that reached its rated endurance (and might go over 100 after that)
+	WearLevel,
+	/// total amount of data written by the host
+	HostWrites,
+}
+
+impl Kind {
+	/// Machine-friendly name of the metric, with its unit as a suffix (following Prometheus naming conventions).
+	pub fn key(self) -> &'static str {
+		use self::Kind::*;
+		match self {
+			ReallocatedSectors => "reallocated_sectors",
+			PendingSectors => "pending_sectors",
+			PowerOnHours => "power_on_hours",
+			Temperature => "temperature_celsius",
+			WearLevel => "wear_level_percent",
+			HostWrites => "host_writes_bytes",
+		}
+	}
+
+	pub fn unit(self) -> Unit {
+		use self::Kind::*;
+		match self {
+			ReallocatedSectors | PendingSectors => Unit::Sectors,
+			PowerOnHours => Unit::Hours,
+			Temperature => Unit::Celsius,
+			WearLevel => Unit::Percent,
+			HostWrites => Unit::Bytes,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Unit { Sectors, Hours, Celsius, Percent, Bytes }
+
+impl Unit {
+	pub fn name(self) -> &'static str {
+		use self::Unit::*;
+		match self {
+			Sectors => "sectors",
+			Hours => "hours",
+			Celsius => "celsius",
+			Percent => "percent",
+			Bytes => "bytes",
+		}
+	}
+}
+
+/// Where the value of the metric came from, for the sake of debugging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum Source {
+	/// SMART attribute with given ID
+	Attribute(u8),
+	/// ATA Device Statistics log
+	DeviceStatistics,
+	/// SCSI log page with given number
+	LogPage(u8),
+	/// SCSI grown defect list
+	DefectList,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub struct Metric {
+	pub kind: Kind,
+	/// value, in units of [`kind.unit()`](enum.Kind.html#method.unit)
+	pub value: f64,
+	pub source: Source,
+}
+
+// collects metrics, keeping the first value of every kind; sources are thus expected to be fed in order of preference
+#[derive(Default)]
+struct Metrics(Vec<Metric>);
+
+impl Metrics {
+	fn add(&mut self, kind: Kind, value: Option<f64>, source: Source) {
+		if let Some(value) = value
+			&& !self.0.iter().any(|m| m.kind == kind)
+		{
+			self.0.push(Metric { kind, value, source });
+		}
+	}
+
+	fn into_vec(mut self) -> Vec<Metric> {
+		self.0.sort_by_key(|m| m.kind);
+		self.0
+	}
+}
+
+// plain numeric value of the raw attribute, for formats that have one
+fn raw_number(raw: &Raw) -> Option<f64> {
+	use self::Raw::*;
+	match *raw {
+		Raw64(x) | Hex(x, _) => Some(x as f64),
+		Raw16opt16(x, _) => Some(x as f64),
+		Raw24opt8(x, _) => Some(x as f64),
+		Raw16avg16 { value, .. } => Some(value as f64),
+		_ => None,
+	}
+}
+
+fn power_on_hours(name: &str, raw: &Raw) -> Option<f64> {
+	use self::Raw::*;
+	match *raw {
+		// these are already converted from whatever units drivedb says the drive uses
+		Minutes(x, _) => Some(x as f64 / 60.),
+		Seconds(x) => Some(x as f64 / 3600.),
+		HoursMilliseconds(h, ms) => Some(h as f64 + ms as f64 / 3_600_000.),
+		_ if name == "Power_On_Hours" => raw_number(raw),
+		_ => None,
+	}
+}
+
+fn temperature(raw: &Raw) -> Option<f64> {
+	use self::Raw::*;
+	match *raw {
+		Celsius(x) => Some(x as f64),
+		CelsiusMinMax { current, .. } | CelsiusMinMaxCount { current, .. } => Some(current as f64),
+		// whatever it is, it's not something that drivedb knows to be a temperature
+		_ => None,
+	}
+}
+
+/*
+Attribute names (as they appear in drivedb) that are recognized, for every kind of metric, in order of preference.
+
+Names that are ambiguous (e.g. reallocated *blocks* of some SSDs, or writes to NAND that include write amplification) are deliberately left out.
+*/
+const REALLOCATED: &[&str] = &["Reallocated_Sector_Ct"];
+const PENDING: &[&str] = &["Current_Pending_Sector"];
+const POWER_ON: &[&str] = &["Power_On_Hours", "Power_On_Minutes", "Power_On_Seconds", "Power_On_Half_Minutes", "Power_On_Hours_and_Msec"];
+const TEMPERATURE: &[&str] = &["Temperature_Celsius", "Temperature_Internal", "Airflow_Temperature_Cel", "Temperature_Case"];
+// normalized values of these start at 100 and go down as the device wears out
+const LIFE_LEFT: &[&str] = &["Percent_Lifetime_Remain", "Media_Wearout_Indicator", "Wear_Leveling_Count", "SSD_Life_Left"];
+// multipliers are in bytes; zero stands for logical sector size
+const HOST_WRITES: &[(&str, u64)] = &[
+	("Total_LBAs_Written", 0),
+	("Host_Writes_GiB", 1 << 30),
+	("Total_Writes_GiB", 1 << 30),
+	("Lifetime_Writes_GiB", 1 << 30),
+	("Host_Writes_32MiB", 32 << 20),
+	("Host_Writes_MiB", 1 << 20),
+];
+
+fn named<'a>(attrs: &'a [SmartAttribute], name: &str) -> Option<&'a SmartAttribute> {
+	attrs.iter().find(|attr| attr.name.as_deref() == Some(name))
+}
+
+// finds attribute with one of the `names`, in order of preference
+fn find<'a>(attrs: &'a [SmartAttribute], names: &[&'static str]) -> Option<(&'a SmartAttribute, &'static str)> {
+	names.iter().find_map(|&name| named(attrs, name).map(|attr| (attr, name)))
+}
+
+/**
+Derives metrics from SMART attributes `attrs` and Device Statistics log `stats` of an ATA device; `sector_size` is the logical sector size, in bytes (see `Id::sector_size_log`).
+
+Device Statistics are standardized and thus preferred. Attributes are recognized by their names, so these need to be rendered with the help of [drivedb](../drivedb/index.html).
+
+## Example
+
+```
+use hdd::ata::data::device_stats::{self, DeviceStatistics};
+use hdd::metrics::{self, Kind, Source};
+
+// Device Statistics log page with given statistics (offset, value), all of them supported and valid
+fn page(number: u8, stats: &[(usize, u64)]) -> Vec<u8> {
+	let mut data = vec![0; 512];
+	data[0] = 1; // revision
+	data[2] = number;
+	for &(offset, value) in stats {
+		data[offset .. offset + 8].copy_from_slice(&(value | 0b11 << 62).to_le_bytes());
+	}
+	data
+}
+
+let mut rotating_media = page(0x03, &[(32, 8), (56, 2)]);
+// supported, but not valid
+rotating_media[16 .. 24].copy_from_slice(&(1u64 << 63 | 100).to_le_bytes());
+let rotating_media = device_stats::parse_rotating_media(&rotating_media).unwrap();
+assert_eq!(rotating_media.head_flying_hours, None);
+
+// current, lifetime highest and lowest, and (ignored) lowest average short term temperature
+let temperature = device_stats::parse_temperature(&page(0x05, &[(8, 36), (32, 55), (40, -5i8 as u8 as u64), (56, 30)])).unwrap();
+assert_eq!((temperature.current, temperature.highest, temperature.lowest), (Some(36), Some(55), Some(-5)));
+
+let stats = DeviceStatistics {
+	pages: vec![0x00, 0x01, 0x03, 0x05, 0x07],
+	general: device_stats::parse_general(&page(0x01, &[(16, 12345), (24, 1000)])),
+	rotating_media: Some(rotating_media),
+	temperature: Some(temperature),
+	solid_state: device_stats::parse_solid_state(&page(0x07, &[(8, 7)])),
+};
+
+let metrics = metrics::ata(&[], Some(&stats), 4096);
+assert!(metrics.iter().all(|m| m.source == Source::DeviceStatistics));
+assert_eq!(metrics.iter().map(|m| (m.kind, m.value)).collect::<Vec<_>>(), vec![
+	(Kind::ReallocatedSectors, 8.),
+	(Kind::PendingSectors, 2.),
+	(Kind::PowerOnHours, 12345.),
+	(Kind::Temperature, 36.),
+	(Kind::WearLevel, 7.),
+	(Kind::HostWrites, 1000. * 4096.),
+]);
+```
+*/
+pub fn ata(attrs: &[SmartAttribute], stats: Option<&DeviceStatistics>, sector_size: u32) -> Vec<Metric> {
+	use self::Kind::*;
+
+	let mut m = Metrics::default();
+
+	if let Some(stats) = stats {
+		let s = Source::DeviceStatistics;
+		if let Some(general) = &stats.general {
+			m.add(PowerOnHours, general.power_on_hours.map(|x| x as f64), s);
+			m.add(HostWrites, general.logical_sectors_written.map(|x| x as f64 * sector_size as f64), s);
+		}
+		if let Some(rotating) = &stats.rotating_media {
+			m.add(ReallocatedSectors, rotating.reallocated_sectors.map(|x| x as f64), s);
+			m.add(PendingSectors, rotating.reallocation_candidates.map(|x| x as f64), s);
+		}
+		if let Some(temperature) = &stats.temperature {
+			m.add(Temperature, temperature.current.map(|x| x as f64), s);
+		}
+		if let Some(ssd) = &stats.solid_state {
+			m.add(WearLevel, ssd.percentage_used.map(|x| x as f64), s);
+		}
+	}
+
+	if let Some((attr, _)) = find(attrs, REALLOCATED) {
+		m.add(ReallocatedSectors, raw_number(&attr.raw), Source::Attribute(attr.id));
+	}
+	if let Some((attr, _)) = find(attrs, PENDING) {
+		m.add(PendingSectors, raw_number(&attr.raw), Source::Attribute(attr.id));
+	}
+	if let Some((attr, n)) = find(attrs, POWER_ON) {
+		m.add(PowerOnHours, power_on_hours(n, &attr.raw), Source::Attribute(attr.id));
+	}
+	if let Some((attr, _)) = find(attrs, TEMPERATURE) {
+		m.add(Temperature, temperature(&attr.raw), Source::Attribute(attr.id));
+	}
+	if let Some((attr, _)) = find(attrs, LIFE_LEFT) {
+		// some vendors start counting from 200 or 253, so there's no telling what the percentage is
+		let used = attr.value.filter(|&x| x <= 100).map(|x| 100. - x as f64);
+		m.add(WearLevel, used, Source::Attribute(attr.id));
+	}
+	let host_writes = HOST_WRITES.iter()
+		.find_map(|&(name, multiplier)| named(attrs, name).map(|attr| (attr, multiplier)));
+	if let Some((attr, multiplier)) = host_writes {
+		let multiplier = if multiplier == 0 { sector_size as u64 } else { multiplier };
+		m.add(HostWrites, raw_number(&attr.raw).map(|x| x * multiplier as f64), Source::Attribute(attr.id));
+	}
+
+	m.into_vec()
+}
+
+/// Data that SCSI metrics are derived from; any of it might be missing, as devices implement different sets of log pages.
+#[derive(Debug, Default)]
+pub struct SCSISources<'a> {
+	/// see [`SCSIPages::write_error_counters()`](../scsi/pages/struct.SCSIPages.html#method.write_error_counters)
+	pub write_error_counters: Option<&'a HashMap<ErrorCounter, u64>>,
+	/// see [`SCSIPages::temperature()`](../scsi/pages/struct.SCSIPages.html#method.temperature)
+	pub temperature: Option<u8>,
+	/// see [`SCSIPages::power_on_minutes()`](../scsi/pages/struct.SCSIPages.html#method.power_on_minutes)
+	pub power_on_minutes: Option<u32>,
+	/// see [`SCSIPages::percentage_used()`](../scsi/pages/struct.SCSIPages.html#method.percentage_used)
+	pub percentage_used: Option<u8>,
+	/// number of elements in the grown defect list, see [`SCSICommon::read_defect_data_10()`](../scsi/trait.SCSICommon.html#method.read_defect_data_10)
+	pub grown_defects: Option<u32>,
+}
+
+/// Derives metrics from log pages and defect list of a SCSI device. There's no standard way for SCSI devices to report pending sectors, so this metric is never present.
+pub fn scsi(sources: &SCSISources) -> Vec<Metric> {
+	use self::Kind::*;
+
+	let mut m = Metrics::default();
+
+	m.add(ReallocatedSectors, sources.grown_defects.map(|x| x as f64), Source::DefectList);
+	m.add(PowerOnHours, sources.power_on_minutes.map(|x| x as f64 / 60.), Source::LogPage(0x15));
+	m.add(Temperature, sources.temperature.map(|x| x as f64), Source::LogPage(0x0d));
+	m.add(WearLevel, sources.percentage_used.map(|x| x as f64), Source::LogPage(0x11));
+	m.add(HostWrites, sources.write_error_counters
+		.and_then(|counters| counters.get(&ErrorCounter::BytesProcessed))
+		.map(|&x| x as f64), Source::LogPage(0x02));
+
+	m.into_vec()
+}
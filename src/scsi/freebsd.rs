@@ -10,12 +10,40 @@ use std::io;
 impl SCSIDevice {
 	/// Executes `cmd` and returns tuple of `(sense, data)`.
 	pub(crate) fn do_platform_cmd(&self, cmd: &[u8], dir: Direction, sense_len: usize, data_len: usize)-> Result<(Vec<u8>, Vec<u8>), io::Error> {
-		// might've used Vec::with_capacity(), but this requires rebuilding with Vec::from_raw_parts() later on to hint actual size of data in buffer vecs,
-		// and we're not expecting this function to be someone's bottleneck
-		let mut sense = vec![0; sense_len];
 		let mut data = vec![0; data_len];
 
+		let flags = {
+			use self::Direction::*;
+			match dir {
+				From => ccb_flags_CAM_DIR_IN,
+				To => unimplemented!(), // use do_platform_cmd_out() instead
+				Both => unimplemented!(), //CAM_DIR_BOTH,
+				None => ccb_flags_CAM_DIR_NONE,
+			}
+		};
+
 		let timeout = 10; // in seconds; TODO configurable
+		let (sense, data_len) = self.csio(cmd, flags, &mut data, sense_len, timeout)?;
+		data.truncate(data_len);
+
+		Ok((sense, data))
+	}
+
+	/// Executes `cmd` that transfers `data` to the device, returns sense.
+	pub(crate) fn do_platform_cmd_out(&self, cmd: &[u8], data: &[u8], sense_len: usize, timeout: u32) -> Result<Vec<u8>, io::Error> {
+		// CAM only reads from the buffer with CAM_DIR_OUT, yet it wants a mutable pointer
+		let mut data = data.to_vec();
+
+		let (sense, _) = self.csio(cmd, ccb_flags_CAM_DIR_OUT, &mut data, sense_len, timeout)?;
+
+		Ok(sense)
+	}
+
+	// common part of `do_platform_cmd()` and `do_platform_cmd_out()`; returns sense and the number of bytes actually transferred
+	fn csio(&self, cmd: &[u8], flags: ccb_flags, data: &mut [u8], sense_len: usize, timeout: u32) -> Result<(Vec<u8>, usize), io::Error> {
+		// might've used Vec::with_capacity(), but this requires rebuilding with Vec::from_raw_parts() later on to hint actual size of data in buffer vecs,
+		// and we're not expecting this function to be someone's bottleneck
+		let mut sense = vec![0; sense_len];
 
 		let dev = &self.device.dev;
 
@@ -27,21 +55,12 @@ impl SCSIDevice {
 			// cannot use cam_fill_csio() here: it is defined right in cam/cam_ccb.h
 			// besides, it is a pretty simple function of dubious benefit: sure it's less things to type, but with huge number of arguments it's less clear what's actually filled in a struct
 			csio.ccb_h.func_code = xpt_opcode_XPT_SCSI_IO;
-			csio.ccb_h.flags = {
-				use self::Direction::*;
-				match dir {
-					// TODO &[u8] arg → data → csio.data_ptr for Direction::{To,Both}
-					From => ccb_flags_CAM_DIR_IN,
-					To => unimplemented!(), //CAM_DIR_OUT,
-					Both => unimplemented!(), //CAM_DIR_BOTH,
-					None => ccb_flags_CAM_DIR_NONE,
-				}
-			};
+			csio.ccb_h.flags = flags;
 			csio.ccb_h.xflags = 0;
 			csio.ccb_h.retry_count = 1;
-			csio.ccb_h.timeout = timeout*1000;
+			csio.ccb_h.timeout = timeout.saturating_mul(1000);
 			csio.data_ptr = data.as_mut_ptr();
-			csio.dxfer_len = data.capacity() as u32;
+			csio.dxfer_len = data.len() as u32;
 			csio.sense_len = sense.capacity() as u8;
 			csio.tag_action = MSG_SIMPLE_Q_TAG as u8;
 
@@ -91,9 +110,7 @@ impl SCSIDevice {
 			ccb.csio().dxfer_len - ccb.csio().resid
 		};
 
-		Ok((
-			sense[ .. sense_len as usize].to_vec(),
-			data[ .. data_len as usize].to_vec(),
-		))
+		sense.truncate(sense_len as usize);
+		Ok((sense, data_len as usize))
 	}
 }
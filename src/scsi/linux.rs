@@ -50,25 +50,18 @@ struct sg_io_hdr {
 }
 
 impl SCSIDevice {
-	pub(crate) fn do_platform_cmd(&self, cmd: &[u8], dir: Direction, sense_len: usize, data_len: usize) -> Result<(Vec<u8>, Vec<u8>), io::Error> {
+	// common part of `do_platform_cmd()` and `do_platform_cmd_out()`; returns sense and the number of bytes actually transferred
+	fn sg_io(&self, cmd: &[u8], dxfer_direction: c_int, data: &mut [u8], sense_len: usize, timeout: u32) -> Result<(Vec<u8>, usize), io::Error> {
 		// might've used Vec::with_capacity(), but this requires rebuilding with Vec::from_raw_parts() later on to hint actual size of data in buffer vecs,
 		// and we're not expecting this function to be someone's bottleneck
 		let mut sense = vec![0; sense_len];
-		let mut data = vec![0; data_len];
 
 		let hdr = sg_io_hdr {
 			interface_id:	'S' as c_int,
 
-			dxfer_direction: match dir {
-				// see scsi/sg.h, constants SG_DXFER_{NONE,{TO,FROM,TO_FROM}_DEV}
-				// TODO &[u8] arg → data → sg_io_hdr.dxferp for Direction::{To,Both}
-				Direction::None => -1,
-				Direction::To => unimplemented!(), //-2,
-				Direction::From => -3,
-				Direction::Both => unimplemented!(), //-4,
-			},
+			dxfer_direction,
 			dxferp:	data.as_mut_ptr() as *mut c_void,
-			dxfer_len:	data.capacity() as c_uint,
+			dxfer_len:	data.len() as c_uint,
 			resid:	0,
 
 			sbp:	sense.as_mut_ptr(),
@@ -82,7 +75,7 @@ impl SCSIDevice {
 			host_status:	0,
 			driver_status:	0,
 
-			timeout:	10000,	// TODO configurable
+			timeout:	timeout.saturating_mul(1000),
 			duration:	0,
 
 			iovec_count:	0,
@@ -106,9 +99,34 @@ impl SCSIDevice {
 		// XXX sg_io set resid to 0 for SATA disks, and Hitachi SAS disks behind Adaptec also set this to 0 for things like LOG SENSE 0fh/00h—need more reading/testing
 		let data_len = hdr.dxfer_len - max(hdr.resid, 0) as u32;
 
-		Ok((
-			sense[ .. hdr.sb_len_wr as usize].to_vec(),
-			data[ .. data_len as usize].to_vec(),
-		))
+		sense.truncate(hdr.sb_len_wr as usize);
+		Ok((sense, data_len as usize))
+	}
+
+	pub(crate) fn do_platform_cmd(&self, cmd: &[u8], dir: Direction, sense_len: usize, data_len: usize) -> Result<(Vec<u8>, Vec<u8>), io::Error> {
+		let mut data = vec![0; data_len];
+
+		let dxfer_direction = match dir {
+			// see scsi/sg.h, constants SG_DXFER_{NONE,{TO,FROM,TO_FROM}_DEV}
+			Direction::None => -1,
+			Direction::To => unimplemented!(), // use do_platform_cmd_out() instead
+			Direction::From => -3,
+			Direction::Both => unimplemented!(), //-4,
+		};
+
+		let (sense, data_len) = self.sg_io(cmd, dxfer_direction, &mut data, sense_len, 10)?; // TODO configurable timeout
+		data.truncate(data_len);
+
+		Ok((sense, data))
+	}
+
+	/// Executes `cmd` that transfers `data` to the device, returns sense.
+	pub(crate) fn do_platform_cmd_out(&self, cmd: &[u8], data: &[u8], sense_len: usize, timeout: u32) -> Result<Vec<u8>, io::Error> {
+		// SG_IO only reads from the buffer when sending data to the device, yet it wants a mutable pointer
+		let mut data = data.to_vec();
+
+		let (sense, _) = self.sg_io(cmd, -2, &mut data, sense_len, timeout)?;
+
+		Ok(sense)
 	}
 }
@@ -70,7 +70,7 @@ fn check_sense(sense: &[u8]) -> Result<(), Error> {
 			Some((key, asc, ascq)) => {
 				use sense::key::SenseKey::*;
 				match sense::key::SenseKey::from(key) {
-					Ok | Recovered | Completed => Ok(()),
+					Ok | Recovered | Completed => Result::Ok(()),
 					key => Err(Error::Sense(key, asc, ascq)),
 				}
 			},
@@ -125,6 +125,24 @@ impl SCSIDevice {
 		}
 		ret
 	}
+
+	/// Executes `cmd` that transfers `data` to the device, waiting up to `timeout` seconds for it to complete, and returns sense.
+	pub fn do_cmd_out(&self, cmd: &[u8], data: &[u8], sense_len: usize, timeout: u32) -> Result<Vec<u8>, io::Error> {
+		info!("SCSI cmd: dir=To cmd={:02x?} timeout={}", cmd, timeout);
+		// N.B. data might contain passwords and such, hence no dumping it here
+
+		// this one is implemented in `mod {linux,freebsd}`
+		let ret = Self::do_platform_cmd_out(self, cmd, data, sense_len, timeout);
+		match &ret {
+			Ok(sense) => {
+				debug!("SCSI autosense: {}", hexdump_8(sense));
+			},
+			err => {
+				debug!("SCSI err: {:?}", err);
+			}
+		}
+		ret
+	}
 }
 
 // TODO pub? see read_defect_data_*()
@@ -147,10 +165,30 @@ pub enum DefectList {
 	// why would anyone send READ DEFECT DATA with req_{p,g}list set to 0?
 }
 
+/// Values for the POWER CONDITION field of START STOP UNIT command, see SBC-3, 5.25
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerCondition {
+	/// Process START and LOEJ bits instead of changing power condition
+	StartValid = 0x0,
+	Active = 0x1,
+	Idle = 0x2,
+	Standby = 0x3,
+	/// Transfer control of power conditions back to the logical unit (i.e. re-enable its timers)
+	LUControl = 0x7,
+	/// Force Idle condition timer to zero
+	ForceIdle0 = 0xa,
+	/// Force Standby condition timer to zero
+	ForceStandby0 = 0xb,
+}
+
 // TODO look for non-empty autosense and turn it into errors where appropriate
 pub trait SCSICommon: Sized {
 	// XXX DRY
 	fn do_cmd(&self, cmd: &[u8], dir: Direction, sense_len: usize, data_len: usize) -> Result<(Vec<u8>, Vec<u8>), io::Error>;
+	/// Data-out counterpart of `do_cmd()`; implementors that cannot transfer data to the device can leave this as is.
+	fn do_cmd_out(&self, _cmd: &[u8], _data: &[u8], _sense_len: usize, _timeout: u32) -> Result<Vec<u8>, io::Error> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "data-out commands are not supported"))
+	}
 
 	fn scsi_inquiry(&self, vital: bool, code: u8) -> Result<(Vec<u8>, Vec<u8>), Error> {
 		info!("issuing INQUIRY: code={:?} vital={:?}", code, vital);
@@ -293,103 +331,164 @@ pub trait SCSICommon: Sized {
 		Ok((sense, data))
 	}
 
+	/**
+	Executes START STOP UNIT command, returning sense data.
+
+	Arguments are:
+
+	- `immediate`: whether to return right away instead of waiting for the operation to complete
+	- `power_condition`: power condition to transition into; use `PowerCondition::StartValid` to spin the device up or down according to `start`
+	- `start`: whether to make the logical unit ready (`true`) or stop it (`false`); ignored unless `power_condition` is `PowerCondition::StartValid`
+	*/
+	fn start_stop_unit(&self, immediate: bool, power_condition: PowerCondition, start: bool) -> Result<Vec<u8>, Error> {
+		info!("issuing START STOP UNIT: immediate={:?} power_condition={:?} start={:?}", immediate, power_condition, start);
+
+		let cmd: [u8; 6] = [
+			0x1b, // opcode
+			if immediate {1} else {0}, // reserved × 7, immed
+			0, // reserved
+			0, // reserved × 4, power condition modifier
+			((power_condition as u8) << 4) + if start {1} else {0}, // power condition × 4, reserved, no_flush, loej, start
+			0, // control (XXX what's that?!)
+		];
+
+		let (sense, _) = self.do_cmd(&cmd, Direction::None, 32, 0)?;
+		check_sense(&sense)?;
+		Ok(sense)
+	}
+
 	fn ata_pass_through_16(&self, dir: Direction, regs: &ata::RegistersWrite) -> Result<(ata::RegistersRead, Vec<u8>), ATAError> {
 		info!("issuing ATA PASS-THROUGH (16): dir={:?} regs={:?}", dir, regs);
 
-		// see T10/04-262r8a ATA Command Pass-Through, 3.2.3
-		let extend = 0; // TODO
 		let protocol = match dir {
 			Direction::None => 3, // Non-data
 			Direction::From => 4, // PIO Data-In
-			Direction::To => unimplemented!(), //5, // PIO Data-Out
+			Direction::To => unimplemented!(), // use ata_pass_through_16_out() instead
 			_ => unimplemented!(),
 		};
-		let multiple_count = 0; // TODO
-		let ata_cmd: [u8; 16] = [
-			0x85, // opcode: ATA PASS-THROUGH (16)
-			(multiple_count << 5) + (protocol << 1) + extend,
-			// 0b00: wait up to 2^(OFF_LINE+1)-2 seconds for valid ATA status register
-			// 0b1: CK_COND, return ATA register info in the sense data
-			// 0b0: reserved
-			// 0b1: T_DIR; transfer from ATA device
-			// 0b1: BYT_BLOK; T_LENGTH is in blocks, not in bytes
-			// 0b01: T_LENGTH itself
-			0b0010_1101,
-			0, regs.features,
-			0, regs.sector_count,
-			0, regs.sector,
-			0, regs.cyl_low,
-			0, regs.cyl_high,
-			regs.device,
-			regs.command,
-			0, // control (XXX what's that?!)
-		];
+		// 0b00: wait up to 2^(OFF_LINE+1)-2 seconds for valid ATA status register
+		// 0b1: CK_COND, return ATA register info in the sense data
+		// 0b0: reserved
+		// 0b1: T_DIR; transfer from ATA device
+		// 0b1: BYT_BLOK; T_LENGTH is in blocks, not in bytes
+		// 0b01: T_LENGTH itself
+		let ata_cmd = ata_pass_through_16_cdb(protocol, 0b0010_1101, regs);
 
 		let (sense, data) = self.do_cmd(&ata_cmd, Direction::From, 32, 512)?;
 
-		let sense = match sense::parse(&sense) {
-			Some((true, sense)) => sense,
-			Some((false, _)) | None => {
-				// no (current) sense
-				return Err(ATAError::NoRegisters);
-			},
-		};
+		Ok((parse_ata_pass_through_sense(&sense, regs)?, data))
+	}
 
-		let descriptors = match sense {
-			// current sense in the descriptor format
-			sense::Sense::Descriptor(sense::DescriptorData {
-				descriptors,
-				// Recovered Error / ATA PASS THROUGH INFORMATION AVAILABLE
-				key: 0x01, asc: 0x00, ascq: 0x1D,
-				..
-			}) => {
-				descriptors
-			},
+	/// Same as `ata_pass_through_16()`, but for PIO Data-Out commands: transfers `data` (which should be a multiple of 512 bytes) to the device, waiting up to `timeout` seconds for the command to complete.
+	fn ata_pass_through_16_out(&self, regs: &ata::RegistersWrite, data: &[u8], timeout: u32) -> Result<ata::RegistersRead, ATAError> {
+		info!("issuing ATA PASS-THROUGH (16): dir=To regs={:?}", regs);
 
-			sense::Sense::Descriptor(sense::DescriptorData {
-				descriptors,
-				// some devices/drivers return (Ok, 0, 0) as a sense;
-				// will validate its contents below
-				key: 0x00, asc: 0x00, ascq: 0x00,
-				..
-			}) => {
-				descriptors
-			},
+		// 0b00: wait up to 2^(OFF_LINE+1)-2 seconds for valid ATA status register
+		// 0b1: CK_COND, return ATA register info in the sense data
+		// 0b0: reserved
+		// 0b0: T_DIR; transfer to ATA device
+		// 0b1: BYT_BLOK; T_LENGTH is in blocks, not in bytes
+		// 0b10: T_LENGTH itself, i.e. the number of blocks is in the Sector Count field
+		let ata_cmd = ata_pass_through_16_cdb(5 /* PIO Data-Out */, 0b0010_0110, regs);
 
-			sense::Sense::Fixed(sense::FixedData::Valid {
-				// Illegal Request / INVALID COMMAND OPERATION CODE
-				key: 0x05, asc: 0x20, ascq: 0x00, ..
-			}) => {
-				return Err(ATAError::NotSupported);
-			},
+		let sense = self.do_cmd_out(&ata_cmd, data, 32, timeout)?;
 
-			// unexpected sense
-			sense => return Err(Error::from_sense(&sense))?,
-		};
+		parse_ata_pass_through_sense(&sense, regs)
+	}
+}
+
+// see T10/04-262r8a ATA Command Pass-Through, 3.2.3
+fn ata_pass_through_16_cdb(protocol: u8, flags: u8, regs: &ata::RegistersWrite) -> [u8; 16] {
+	let extend = if regs.hob.is_some() { 1 } else { 0 };
+	let hob = regs.hob.unwrap_or_default();
+	let multiple_count = 0; // TODO
+	[
+		0x85, // opcode: ATA PASS-THROUGH (16)
+		(multiple_count << 5) + (protocol << 1) + extend,
+		flags,
+		0, regs.features,
+		hob.sector_count, regs.sector_count,
+		hob.sector, regs.sector,
+		hob.cyl_low, regs.cyl_low,
+		hob.cyl_high, regs.cyl_high,
+		regs.device,
+		regs.command,
+		0, // control (XXX what's that?!)
+	]
+}
 
-		for desc in descriptors {
-			if desc.code != 0x09 { continue; }
-			if desc.data.len() != 12 { continue; }
+// looks for ATA Status Return descriptor in the sense returned by ATA PASS-THROUGH
+fn parse_ata_pass_through_sense(sense: &[u8], regs: &ata::RegistersWrite) -> Result<ata::RegistersRead, ATAError> {
+	let sense = match sense::parse(sense) {
+		Some((true, sense)) => sense,
+		Some((false, _)) | None => {
+			// no (current) sense
+			return Err(ATAError::NoRegisters);
+		},
+	};
 
-			let d = desc.data;
+	let descriptors = match sense {
+		// current sense in the descriptor format
+		sense::Sense::Descriptor(sense::DescriptorData {
+			descriptors,
+			// Recovered Error / ATA PASS THROUGH INFORMATION AVAILABLE
+			key: 0x01, asc: 0x00, ascq: 0x1D,
+			..
+		}) => {
+			descriptors
+		},
 
-			// TODO? EXTEND bit, ATA PASS-THROUGH 12 vs 16
-			return Ok((ata::RegistersRead {
-				error: d[1],
+		sense::Sense::Descriptor(sense::DescriptorData {
+			descriptors,
+			// some devices/drivers return (Ok, 0, 0) as a sense;
+			// will validate its contents below
+			key: 0x00, asc: 0x00, ascq: 0x00,
+			..
+		}) => {
+			descriptors
+		},
 
-				sector_count: d[3],
+		sense::Sense::Fixed(sense::FixedData::Valid {
+			// Illegal Request / INVALID COMMAND OPERATION CODE
+			key: 0x05, asc: 0x20, ascq: 0x00, ..
+		}) => {
+			return Err(ATAError::NotSupported);
+		},
 
-				sector: d[5],
-				cyl_low: d[7],
-				cyl_high: d[9],
-				device: d[10],
+		// unexpected sense
+		sense => return Err(Error::from_sense(&sense))?,
+	};
 
-				status: d[11],
-			}, data))
-		}
+	for desc in descriptors {
+		if desc.code != 0x09 { continue; }
+		if desc.data.len() != 12 { continue; }
 
-		return Err(ATAError::NoRegisters);
+		let d = desc.data;
+
+		// TODO? ATA PASS-THROUGH 12 vs 16
+		return Ok(ata::RegistersRead {
+			error: d[1],
+
+			sector_count: d[3],
+
+			sector: d[5],
+			cyl_low: d[7],
+			cyl_high: d[9],
+			device: d[10],
+
+			status: d[11],
+
+			// d[0] has EXTEND bit that tells whether these are valid, but some translators do not bother to set it
+			hob: regs.hob.map(|_| ata::RegistersHOB {
+				sector_count: d[2],
+				sector: d[4],
+				cyl_low: d[6],
+				cyl_high: d[8],
+			}),
+		})
 	}
+
+	return Err(ATAError::NoRegisters);
 }
 
 impl SCSICommon for SCSIDevice {
@@ -397,6 +496,9 @@ impl SCSICommon for SCSIDevice {
 	fn do_cmd(&self, cmd: &[u8], dir: Direction, sense_len: usize, data_len: usize) -> Result<(Vec<u8>, Vec<u8>), io::Error> {
 		Self::do_cmd(self, cmd, dir, sense_len, data_len)
 	}
+	fn do_cmd_out(&self, cmd: &[u8], data: &[u8], sense_len: usize, timeout: u32) -> Result<Vec<u8>, io::Error> {
+		Self::do_cmd_out(self, cmd, data, sense_len, timeout)
+	}
 }
 
 fn read_defect_data<D: SCSICommon, C>(
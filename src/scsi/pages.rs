@@ -26,6 +26,7 @@ if pages.supported_pages().contains(&0x03) {
 use crate::scsi;
 use scsi::{SCSIDevice, SCSICommon};
 use scsi::data::log_page;
+use crate::drivedb::{DriveMeta, LogFormat, LogParameter};
 
 extern crate byteorder;
 use byteorder::{ReadBytesExt, BigEndian};
@@ -104,6 +105,51 @@ pub struct InformationalException {
 	pub vendor_specific: Vec<u8>,
 }
 
+/// Value of log parameter, interpreted according to its [drivedb description](../../drivedb/struct.LogParameter.html)
+#[derive(Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize))]
+pub enum ParameterValue {
+	Uint(u64),
+	ASCII(String),
+	/// raw bytes, either because the description says so, or because the value does not fit `u64`
+	Hex(Vec<u8>),
+}
+
+impl ParameterValue {
+	fn new(format: LogFormat, value: &[u8]) -> Self {
+		match format {
+			LogFormat::Uint => {
+				// see error_counters() on why there might be more than 8 bytes
+				let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+				match value.len() - start {
+					0 if !value.is_empty() => ParameterValue::Uint(0),
+					len @ 1..=8 => ParameterValue::Uint((&value[start..]).read_uint::<BigEndian>(len).unwrap()),
+					_ => ParameterValue::Hex(value.to_vec()),
+				}
+			},
+			LogFormat::ASCII => ParameterValue::ASCII(
+				String::from_utf8_lossy(value).trim_end_matches(['\0', ' ']).to_string()
+			),
+			LogFormat::Hex => ParameterValue::Hex(value.to_vec()),
+		}
+	}
+}
+
+impl ::std::fmt::Display for ParameterValue {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		match self {
+			ParameterValue::Uint(x) => write!(f, "{}", x),
+			ParameterValue::ASCII(s) => write!(f, "{}", s),
+			ParameterValue::Hex(bytes) => {
+				for b in bytes {
+					write!(f, "{:02x}", b)?;
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
 /// For a given page number `page`, return its name
 pub fn page_name(page: u8) -> &'static str {
 	match page {
@@ -116,6 +162,8 @@ pub fn page_name(page: u8) -> &'static str {
 		0x0d => "Temperature",
 		0x0e => "Start-Stop Cycle Counter",
 		0x10 => "Self-Test results",
+		0x11 => "Solid State Media",
+		0x15 => "Background Scan Results",
 		0x2f => "Informational Exceptions",
 		0x30..=0x3e => "(Vendor-Specific)",
 		0x3f => "(Reserved)",
@@ -430,6 +478,73 @@ impl<'a> SCSIPages<'a, SCSIDevice> {
 		Ok(self_tests)
 	}
 
+	/// Returns the number of minutes the device was powered on, from Background Scan Results page (SBC-3).
+	pub fn power_on_minutes(&mut self) -> Result<u32, Error> {
+		info!("querying power-on time");
+
+		let params = self.get_params(0x15)?;
+
+		for param in params {
+			// background scan status parameter starts with ACCUMULATED POWER ON MINUTES
+			if param.code != 0 { continue; }
+			if param.value.len() < 4 { continue; }
+
+			return Ok((&param.value[0 .. 4]).read_u32::<BigEndian>().unwrap());
+		}
+
+		Err(Error::InvalidData("find valid param in the page"))
+	}
+
+	/**
+	Returns vendor's estimate of the percentage of device life used, from Solid State Media page (SBC-3). Value of 100 means the device reached its rated endurance; it might go over 100 after that.
+	*/
+	pub fn percentage_used(&mut self) -> Result<u8, Error> {
+		info!("querying percentage used endurance indicator");
+
+		let params = self.get_params(0x11)?;
+
+		for param in params {
+			// bytes 0..2 of the percentage used endurance indicator parameter are reserved
+			if param.code != 0x0001 { continue; }
+			if param.value.len() < 4 { continue; }
+
+			return Ok(param.value[3]);
+		}
+
+		Err(Error::InvalidData("find valid param in the page"))
+	}
+
+	/**
+	Queries log parameters that drivedb entry `meta` has descriptions for (see [`DriveMeta::log_parameters()`](../../drivedb/struct.DriveMeta.html#method.log_parameters)), and interprets their values accordingly.
+
+	Pages that the device does not support are skipped, as are the pages that cannot be read or parsed.
+	*/
+	pub fn described_parameters(&mut self, meta: &DriveMeta) -> Vec<(LogParameter, ParameterValue)> {
+		let mut pages: Vec<u8> = meta.log_parameters().iter().map(|param| param.page).collect();
+		pages.sort_unstable();
+		pages.dedup();
+
+		let mut out = vec![];
+		for page in pages {
+			info!("querying described params of page {}", page);
+
+			let params = match self.get_params(page) {
+				Ok(params) => params,
+				Err(e) => {
+					info!("skipping page {}: {}", page, e);
+					continue;
+				},
+			};
+
+			for param in params {
+				if let Some(desc) = meta.render_log_parameter(page, param.code) {
+					out.push((desc.clone(), ParameterValue::new(desc.format, &param.value)));
+				}
+			}
+		}
+		out
+	}
+
 	pub fn informational_exceptions(&mut self) -> Result<Vec<InformationalException>, Error> {
 		info!("querying informational exceptions");
 